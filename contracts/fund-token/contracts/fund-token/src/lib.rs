@@ -1,7 +1,17 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracterror, contracttype, Address, Env, String, symbol_short};
+use soroban_sdk::{contract, contractclient, contractimpl, contracterror, contracttype, Address, BytesN, Env, String, Vec, symbol_short};
 
 const DECIMALS: u32 = 7;
+// Atraso mínimo do timelock, em ledgers (~1 dia a 5 s por ledger).
+pub const MIN_DELAY: u32 = 17_280;
+// Escala do acumulador de rendimento por cota.
+const REWARD_PRECISION: i128 = 1_000_000_000_000_000_000;
+
+/// Contrato consultado a cada emissão (o vault, para a razão de subordinação).
+#[contractclient(name = "MintGuardClient")]
+pub trait MintGuard {
+    fn on_mint(env: Env, token: Address, quotas: i128, supply: i128) -> bool;
+}
 
 #[derive(Clone)]
 #[contracttype]
@@ -15,6 +25,38 @@ pub enum DataKey {
     Balance(Address),
     Whitelist(Address),
     Paused,
+    Distributor,                // contrato (vault) autorizado a lançar rendimentos
+    RewardPerShare,             // acumulado por cota, escalado por REWARD_PRECISION
+    RewardDust,                 // sobra da divisão do último lançamento, somada no próximo
+    RewardCheckpoint(Address),  // RewardPerShare na última liquidação do cotista
+    RewardAccrued(Address),     // rendimento liquidado e ainda não sacado
+    MintGuard,                  // contrato que autoriza cada emissão (opcional)
+    Minter(Address),            // contrato (vault) autorizado a emitir cotas nas subscrições
+    Guardian,                   // pode cancelar operações agendadas no timelock
+    TimelockDelay,              // atraso mínimo, em ledgers, entre agendar e executar
+    TimelockOp(u32),            // op_id -> ScheduledOp
+    TimelockCount,
+}
+
+/// Operações de governança que só valem depois do atraso do timelock.
+#[derive(Clone)]
+#[contracttype]
+pub enum TimelockOp {
+    SetMaxSupply(i128),
+    Upgrade(BytesN<32>),        // hash do novo WASM já instalado
+    WhitelistRemove(Vec<Address>),
+    SetDelay(u32),
+    SetGuardian(Address),
+    SetDistributor(Address),
+    SetMinter(Address, bool),
+    SetMintGuard(Address),
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct ScheduledOp {
+    pub op: TimelockOp,
+    pub eta: u32,               // ledger a partir do qual pode ser executada
 }
 
 #[contracterror]
@@ -27,6 +69,21 @@ pub enum TokenError {
     InsufficientBalance = 5,
     SupplyOverflow = 6,
     InvalidAmount = 7,
+    NotDistributor = 8,
+    NoSupply = 9,
+    MintBlocked = 10,
+    NotFound = 11,
+    NotReady = 12,
+    NoTimelock = 13,
+    NotMinter = 14,
+    Timelocked = 15,
+    DelayTooShort = 16,
+}
+
+/// Mudanças sensíveis só valem diretamente antes de `init_timelock`; depois, via `schedule`.
+fn ensure_no_timelock(env: &Env) -> Result<(), TokenError> {
+    if env.storage().instance().has(&DataKey::Guardian) { return Err(TokenError::Timelocked); }
+    Ok(())
 }
 
 fn ensure_positive(amount: i128) -> Result<(), TokenError> {
@@ -40,6 +97,25 @@ fn sub(a: i128, b: i128, e: TokenError) -> Result<i128, TokenError> {
     a.checked_sub(b).ok_or(e)
 }
 
+fn reward_per_share(env: &Env) -> i128 {
+    env.storage().instance().get(&DataKey::RewardPerShare).unwrap_or(0)
+}
+/// Rendimento ainda não liquidado de `addr` desde o último checkpoint.
+fn unsettled_reward(env: &Env, addr: &Address, balance: i128) -> i128 {
+    let checkpoint: i128 = env.storage().persistent().get(&DataKey::RewardCheckpoint(addr.clone())).unwrap_or(0);
+    balance * (reward_per_share(env) - checkpoint) / REWARD_PRECISION
+}
+/// Liquida o rendimento de `addr` com o saldo atual. Deve rodar antes de qualquer mudança de saldo.
+fn settle(env: &Env, addr: &Address) {
+    let balance: i128 = env.storage().persistent().get(&DataKey::Balance(addr.clone())).unwrap_or(0);
+    let pending = unsettled_reward(env, addr, balance);
+    if pending > 0 {
+        let accrued: i128 = env.storage().persistent().get(&DataKey::RewardAccrued(addr.clone())).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::RewardAccrued(addr.clone()), &(accrued + pending));
+    }
+    env.storage().persistent().set(&DataKey::RewardCheckpoint(addr.clone()), &reward_per_share(env));
+}
+
 #[contract]
 pub struct FundToken;
 
@@ -69,14 +145,16 @@ impl FundToken {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
         env.storage().persistent().set(&DataKey::Whitelist(address.clone()), &true);
-        env.events().publish((symbol_short!("whitelist_add"),), address);
+        env.events().publish((symbol_short!("wl_add"),), address);
         Ok(())
     }
+    /// Com o timelock configurado, a remoção passa por `TimelockOp::WhitelistRemove`.
     pub fn whitelist_remove(env: Env, address: Address) -> Result<(), TokenError> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
+        ensure_no_timelock(&env)?;
         env.storage().persistent().remove(&DataKey::Whitelist(address.clone()));
-        env.events().publish((symbol_short!("whitelist_remove"),), address);
+        env.events().publish((symbol_short!("wl_rm"),), address);
         Ok(())
     }
     pub fn is_whitelisted(env: Env, address: Address) -> bool {
@@ -84,11 +162,49 @@ impl FundToken {
     }
 
     // --- Mint / Transfer ---
-    pub fn mint(env: Env, to: Address, amount: i128) -> Result<(), TokenError> {
+    /// Emissão pelo admin. Se o mint guard recusar, não emite e retorna false sem reverter, para
+    /// que o alerta do guard (ex.: `sub_alert` do vault) fique gravado.
+    pub fn mint(env: Env, to: Address, amount: i128) -> Result<bool, TokenError> {
         ensure_positive(amount)?;
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
-        if env.storage().instance().get::<bool>(&DataKey::Paused).unwrap_or(false) {
+        Self::issue(env, to, amount)
+    }
+
+    /// Emissão por um minter autorizado (o vault, ao liquidar uma subscrição). Recusa do mint
+    /// guard falha com `MintBlocked`.
+    pub fn mint_by(env: Env, minter: Address, to: Address, amount: i128) -> Result<(), TokenError> {
+        ensure_positive(amount)?;
+        minter.require_auth();
+        if !Self::is_minter(env.clone(), minter) { return Err(TokenError::NotMinter); }
+        if !Self::issue(env, to, amount)? { return Err(TokenError::MintBlocked); }
+        Ok(())
+    }
+
+    /// Com o timelock configurado, a mudança passa por `TimelockOp::SetMinter`.
+    pub fn set_minter(env: Env, minter: Address, allowed: bool) -> Result<(), TokenError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        ensure_no_timelock(&env)?;
+        Self::write_minter(&env, minter, allowed);
+        Ok(())
+    }
+
+    fn write_minter(env: &Env, minter: Address, allowed: bool) {
+        if allowed {
+            env.storage().persistent().set(&DataKey::Minter(minter.clone()), &true);
+        } else {
+            env.storage().persistent().remove(&DataKey::Minter(minter.clone()));
+        }
+        env.events().publish((symbol_short!("minter"), minter), allowed);
+    }
+    pub fn is_minter(env: Env, minter: Address) -> bool {
+        env.storage().persistent().get(&DataKey::Minter(minter)).unwrap_or(false)
+    }
+
+    /// Retorna false (sem emitir) se o mint guard recusar.
+    fn issue(env: Env, to: Address, amount: i128) -> Result<bool, TokenError> {
+        if env.storage().instance().get::<_, bool>(&DataKey::Paused).unwrap_or(false) {
             return Err(TokenError::Paused);
         }
         if !Self::is_whitelisted(env.clone(), to.clone()) {
@@ -100,19 +216,27 @@ impl FundToken {
         let new_supply = add(cur_supply, amount, TokenError::SupplyOverflow)?;
         if new_supply > max_supply { return Err(TokenError::SupplyOverflow); }
 
+        if let Some(guard) = env.storage().instance().get::<_, Address>(&DataKey::MintGuard) {
+            if !MintGuardClient::new(&env, &guard).on_mint(&env.current_contract_address(), &amount, &cur_supply) {
+                env.events().publish((symbol_short!("mint_blk"), to), amount);
+                return Ok(false);
+            }
+        }
+
         let bal = Self::balance(env.clone(), to.clone());
         let new_bal = add(bal, amount, TokenError::SupplyOverflow)?;
 
+        settle(&env, &to);
         env.storage().instance().set(&DataKey::TotalSupply, &new_supply);
         env.storage().persistent().set(&DataKey::Balance(to.clone()), &new_bal);
         env.events().publish((symbol_short!("mint"), to), amount);
-        Ok(())
+        Ok(true)
     }
 
     pub fn transfer(env: Env, from: Address, to: Address, amount: i128) -> Result<bool, TokenError> {
         ensure_positive(amount)?;
         from.require_auth();
-        if env.storage().instance().get::<bool>(&DataKey::Paused).unwrap_or(false) {
+        if env.storage().instance().get::<_, bool>(&DataKey::Paused).unwrap_or(false) {
             return Err(TokenError::Paused);
         }
         if !Self::is_whitelisted(env.clone(), to.clone()) {
@@ -124,12 +248,195 @@ impl FundToken {
         let from_new = sub(from_bal, amount, TokenError::InsufficientBalance)?;
         let to_new   = add(to_bal, amount, TokenError::SupplyOverflow)?;
 
+        settle(&env, &from);
+        settle(&env, &to);
         env.storage().persistent().set(&DataKey::Balance(from.clone()), &from_new);
         env.storage().persistent().set(&DataKey::Balance(to.clone()), &to_new);
         env.events().publish((symbol_short!("transfer"), from, to), amount);
         Ok(true)
     }
 
+    /// Queima cotas de `from` (o vault, ao liquidar resgates).
+    pub fn burn(env: Env, from: Address, amount: i128) -> Result<(), TokenError> {
+        ensure_positive(amount)?;
+        from.require_auth();
+        if env.storage().instance().get::<_, bool>(&DataKey::Paused).unwrap_or(false) {
+            return Err(TokenError::Paused);
+        }
+        let bal = Self::balance(env.clone(), from.clone());
+        let new_bal = sub(bal, amount, TokenError::InsufficientBalance)?;
+        let supply = sub(Self::total_supply(env.clone()), amount, TokenError::InsufficientBalance)?;
+
+        settle(&env, &from);
+        env.storage().persistent().set(&DataKey::Balance(from.clone()), &new_bal);
+        env.storage().instance().set(&DataKey::TotalSupply, &supply);
+        env.events().publish((symbol_short!("burn"), from), amount);
+        Ok(())
+    }
+
+    // --- Rendimentos (modelo pull) ---
+    // O vault lança cada pagamento em O(1) no acumulador RewardPerShare; cada cotista é
+    // liquidado quando o seu saldo muda e saca pelo vault (claim).
+    /// Define o distributor. Trocar um distributor já definido só via timelock, porque ele
+    /// saca o rendimento de qualquer cotista em `take_reward`.
+    pub fn set_distributor(env: Env, distributor: Address) -> Result<(), TokenError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if env.storage().instance().has(&DataKey::Distributor) { return Err(TokenError::Timelocked); }
+        Self::write_distributor(&env, distributor);
+        Ok(())
+    }
+
+    fn write_distributor(env: &Env, distributor: Address) {
+        env.storage().instance().set(&DataKey::Distributor, &distributor);
+        env.events().publish((symbol_short!("distrib"),), distributor);
+    }
+
+    pub fn add_reward(env: Env, amount: i128) -> Result<(), TokenError> {
+        ensure_positive(amount)?;
+        let distributor: Address = env.storage().instance().get(&DataKey::Distributor).ok_or(TokenError::NotDistributor)?;
+        distributor.require_auth();
+        let supply = Self::total_supply(env.clone());
+        if supply <= 0 { return Err(TokenError::NoSupply); }
+
+        // a sobra da divisão entra no próximo lançamento: nada se perde no acumulador
+        let dust: i128 = env.storage().instance().get(&DataKey::RewardDust).unwrap_or(0);
+        let scaled = amount.checked_mul(REWARD_PRECISION).and_then(|v| v.checked_add(dust)).ok_or(TokenError::SupplyOverflow)?;
+        let acc = add(reward_per_share(&env), scaled / supply, TokenError::SupplyOverflow)?;
+        env.storage().instance().set(&DataKey::RewardPerShare, &acc);
+        env.storage().instance().set(&DataKey::RewardDust, &(scaled % supply));
+        env.events().publish((symbol_short!("reward"),), amount);
+        Ok(())
+    }
+
+    /// Zera e retorna o rendimento acumulado de `holder`. Só o distributor (vault) chama, no claim.
+    pub fn take_reward(env: Env, holder: Address) -> Result<i128, TokenError> {
+        let distributor: Address = env.storage().instance().get(&DataKey::Distributor).ok_or(TokenError::NotDistributor)?;
+        distributor.require_auth();
+        settle(&env, &holder);
+        let amount: i128 = env.storage().persistent().get(&DataKey::RewardAccrued(holder.clone())).unwrap_or(0);
+        env.storage().persistent().remove(&DataKey::RewardAccrued(holder));
+        Ok(amount)
+    }
+
+    /// Zera e retorna o rendimento de `holder` acumulado até o acumulador valer `mark` (o
+    /// prescrito). O que entrou depois de `mark` continua pendente. Se o cotista foi liquidado
+    /// depois de `mark` (movimentou cotas), nada prescreve. Só o distributor (vault) chama.
+    pub fn expire_reward(env: Env, holder: Address, mark: i128) -> Result<i128, TokenError> {
+        let distributor: Address = env.storage().instance().get(&DataKey::Distributor).ok_or(TokenError::NotDistributor)?;
+        distributor.require_auth();
+        if mark < 0 || mark > reward_per_share(&env) { return Err(TokenError::InvalidAmount); }
+        let checkpoint: i128 = env.storage().persistent().get(&DataKey::RewardCheckpoint(holder.clone())).unwrap_or(0);
+        if checkpoint > mark { return Ok(0); }
+
+        let balance = Self::balance(env.clone(), holder.clone());
+        let accrued: i128 = env.storage().persistent().get(&DataKey::RewardAccrued(holder.clone())).unwrap_or(0);
+        let expired = accrued + balance * (mark - checkpoint) / REWARD_PRECISION;
+        env.storage().persistent().remove(&DataKey::RewardAccrued(holder.clone()));
+        env.storage().persistent().set(&DataKey::RewardCheckpoint(holder.clone()), &mark);
+        if expired > 0 { env.events().publish((symbol_short!("rwd_exp"), holder), expired); }
+        Ok(expired)
+    }
+
+    pub fn pending_reward(env: Env, holder: Address) -> i128 {
+        let accrued: i128 = env.storage().persistent().get(&DataKey::RewardAccrued(holder.clone())).unwrap_or(0);
+        let balance = Self::balance(env.clone(), holder.clone());
+        accrued + unsettled_reward(&env, &holder, balance)
+    }
+    pub fn reward_per_share(env: Env) -> i128 { reward_per_share(&env) }
+    pub fn distributor(env: Env) -> Option<Address> { env.storage().instance().get(&DataKey::Distributor) }
+
+    /// Com o timelock configurado, a troca passa por `TimelockOp::SetMintGuard`.
+    pub fn set_mint_guard(env: Env, guard: Address) -> Result<(), TokenError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        ensure_no_timelock(&env)?;
+        Self::write_mint_guard(&env, guard);
+        Ok(())
+    }
+
+    fn write_mint_guard(env: &Env, guard: Address) {
+        env.storage().instance().set(&DataKey::MintGuard, &guard);
+        env.events().publish((symbol_short!("mint_grd"),), guard);
+    }
+
+    // --- Timelock ---
+    /// Configura guardião e atraso (pelo menos MIN_DELAY) uma única vez; mudanças posteriores
+    /// passam pelo próprio timelock. Daí em diante whitelist_remove, set_minter e set_mint_guard
+    /// também só valem agendados.
+    pub fn init_timelock(env: Env, guardian: Address, delay: u32) -> Result<(), TokenError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if env.storage().instance().has(&DataKey::Guardian) { return Err(TokenError::AlreadyInitialized); }
+        if delay < MIN_DELAY { return Err(TokenError::DelayTooShort); }
+        env.storage().instance().set(&DataKey::Guardian, &guardian);
+        env.storage().instance().set(&DataKey::TimelockDelay, &delay);
+        env.events().publish((symbol_short!("tl_init"), guardian), delay);
+        Ok(())
+    }
+
+    pub fn schedule(env: Env, op: TimelockOp) -> Result<u32, TokenError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let delay: u32 = env.storage().instance().get(&DataKey::TimelockDelay).ok_or(TokenError::NoTimelock)?;
+        match &op {
+            TimelockOp::SetMaxSupply(v) => ensure_positive(*v)?,
+            TimelockOp::SetDelay(d) => if *d < MIN_DELAY { return Err(TokenError::DelayTooShort); },
+            _ => {}
+        }
+        let id: u32 = env.storage().instance().get(&DataKey::TimelockCount).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TimelockCount, &(id + 1));
+        let eta = env.ledger().sequence().saturating_add(delay);
+        env.storage().persistent().set(&DataKey::TimelockOp(id), &ScheduledOp { op: op.clone(), eta });
+        env.events().publish((symbol_short!("tl_sched"), id), (op, eta));
+        Ok(id)
+    }
+
+    pub fn execute(env: Env, op_id: u32) -> Result<(), TokenError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let key = DataKey::TimelockOp(op_id);
+        let scheduled: ScheduledOp = env.storage().persistent().get(&key).ok_or(TokenError::NotFound)?;
+        if env.ledger().sequence() < scheduled.eta { return Err(TokenError::NotReady); }
+        env.storage().persistent().remove(&key);
+
+        match scheduled.op {
+            TimelockOp::SetMaxSupply(max_supply) => {
+                if max_supply < Self::total_supply(env.clone()) { return Err(TokenError::SupplyOverflow); }
+                env.storage().instance().set(&DataKey::MaxSupply, &max_supply);
+            }
+            TimelockOp::Upgrade(hash) => env.deployer().update_current_contract_wasm(hash),
+            TimelockOp::WhitelistRemove(addresses) => {
+                for a in addresses.iter() {
+                    env.storage().persistent().remove(&DataKey::Whitelist(a.clone()));
+                    env.events().publish((symbol_short!("wl_rm"),), a);
+                }
+            }
+            TimelockOp::SetDelay(delay) => env.storage().instance().set(&DataKey::TimelockDelay, &delay),
+            TimelockOp::SetGuardian(guardian) => env.storage().instance().set(&DataKey::Guardian, &guardian),
+            TimelockOp::SetDistributor(distributor) => Self::write_distributor(&env, distributor),
+            TimelockOp::SetMinter(minter, allowed) => Self::write_minter(&env, minter, allowed),
+            TimelockOp::SetMintGuard(guard) => Self::write_mint_guard(&env, guard),
+        }
+        env.events().publish((symbol_short!("tl_exec"), op_id), ());
+        Ok(())
+    }
+
+    pub fn cancel(env: Env, op_id: u32) -> Result<(), TokenError> {
+        let guardian: Address = env.storage().instance().get(&DataKey::Guardian).ok_or(TokenError::NoTimelock)?;
+        guardian.require_auth();
+        let key = DataKey::TimelockOp(op_id);
+        if !env.storage().persistent().has(&key) { return Err(TokenError::NotFound); }
+        env.storage().persistent().remove(&key);
+        env.events().publish((symbol_short!("tl_cancel"), op_id), guardian);
+        Ok(())
+    }
+
+    pub fn scheduled_op(env: Env, op_id: u32) -> Option<ScheduledOp> {
+        env.storage().persistent().get(&DataKey::TimelockOp(op_id))
+    }
+    pub fn guardian(env: Env) -> Option<Address> { env.storage().instance().get(&DataKey::Guardian) }
+
     // --- Views / Admin ---
     pub fn set_pause(env: Env, paused: bool) -> Result<(), TokenError> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
@@ -150,3 +457,5 @@ impl FundToken {
     pub fn admin(env: Env) -> Address { env.storage().instance().get(&DataKey::Admin).unwrap() }
     pub fn is_paused(env: Env) -> bool { env.storage().instance().get(&DataKey::Paused).unwrap_or(false) }
 }
+
+mod test;
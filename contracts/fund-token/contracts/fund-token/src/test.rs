@@ -1,21 +1,155 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{vec, Env, String};
+use soroban_sdk::{testutils::{Address as _, Ledger as _}, Env, String};
 
-#[test]
-fn test() {
+struct Setup<'a> {
+    env: Env,
+    client: FundTokenClient<'a>,
+    distributor: Address,
+}
+
+fn setup<'a>() -> Setup<'a> {
     let env = Env::default();
-    let contract_id = env.register(Contract, ());
-    let client = ContractClient::new(&env, &contract_id);
-
-    let words = client.hello(&String::from_str(&env, "Dev"));
-    assert_eq!(
-        words,
-        vec![
-            &env,
-            String::from_str(&env, "Hello"),
-            String::from_str(&env, "Dev"),
-        ]
-    );
+    env.mock_all_auths();
+    let contract_id = env.register(FundToken, ());
+    let client = FundTokenClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "Cota FIDC"), &String::from_str(&env, "FIDC1"), &1_000_000);
+    let distributor = Address::generate(&env);
+    client.set_distributor(&distributor);
+    Setup { env, client, distributor }
+}
+
+fn investor(t: &Setup) -> Address {
+    let a = Address::generate(&t.env);
+    t.client.whitelist_add(&a);
+    a
+}
+
+#[test]
+fn mint_and_burn_respect_whitelist_supply_and_pause() {
+    let t = setup();
+    let a = investor(&t);
+    let outsider = Address::generate(&t.env);
+
+    assert_eq!(t.client.try_mint(&outsider, &10), Err(Ok(TokenError::NotWhitelisted)));
+    assert_eq!(t.client.try_mint(&a, &0), Err(Ok(TokenError::InvalidAmount)));
+    assert_eq!(t.client.try_mint(&a, &1_000_001), Err(Ok(TokenError::SupplyOverflow)));
+    t.client.mint(&a, &600);
+    assert_eq!((t.client.balance(&a), t.client.total_supply()), (600, 600));
+
+    t.client.set_pause(&true);
+    assert_eq!(t.client.try_mint(&a, &1), Err(Ok(TokenError::Paused)));
+    assert_eq!(t.client.try_burn(&a, &1), Err(Ok(TokenError::Paused)));
+    t.client.set_pause(&false);
+
+    assert_eq!(t.client.try_burn(&a, &601), Err(Ok(TokenError::InsufficientBalance)));
+    t.client.burn(&a, &200);
+    assert_eq!((t.client.balance(&a), t.client.total_supply()), (400, 400));
+
+    let minter = Address::generate(&t.env);
+    assert_eq!(t.client.try_mint_by(&minter, &a, &1), Err(Ok(TokenError::NotMinter)));
+    t.client.set_minter(&minter, &true);
+    t.client.mint_by(&minter, &a, &100);
+    assert_eq!(t.client.balance(&a), 500);
+}
+
+#[test]
+fn reward_dust_carries_into_next_launch() {
+    let t = setup();
+    let (a, b, c) = (investor(&t), investor(&t), investor(&t));
+    for h in [&a, &b, &c] { t.client.mint(h, &1); }
+
+    // 10 / 3 não fecha: a sobra fica em RewardDust e nada se perde
+    t.client.add_reward(&10);
+    assert_eq!(t.client.pending_reward(&a), 3);
+    t.client.add_reward(&2);
+    for h in [&a, &b, &c] { assert_eq!(t.client.pending_reward(h), 4); }
+
+    assert_eq!(t.client.take_reward(&a), 4);
+    assert_eq!(t.client.pending_reward(&a), 0);
+    assert_eq!(t.client.pending_reward(&b), 4);
+}
+
+#[test]
+fn transfer_settles_rewards_at_the_old_balance() {
+    let t = setup();
+    let (a, b) = (investor(&t), investor(&t));
+    t.client.mint(&a, &300);
+    t.client.mint(&b, &100);
+    t.client.add_reward(&400);
+
+    // a transfere metade: o rendimento já lançado fica com quem tinha as cotas
+    t.client.transfer(&a, &b, &150);
+    assert_eq!((t.client.pending_reward(&a), t.client.pending_reward(&b)), (300, 100));
+    t.client.add_reward(&400);
+    assert_eq!((t.client.pending_reward(&a), t.client.pending_reward(&b)), (450, 350));
+
+    // mint e burn também liquidam antes de mudar o saldo
+    t.client.burn(&b, &250);
+    t.client.mint(&a, &50);
+    t.client.add_reward(&200);
+    assert_eq!((t.client.pending_reward(&a), t.client.pending_reward(&b)), (650, 350));
+    assert_eq!(t.client.try_transfer(&a, &Address::generate(&t.env), &1), Err(Ok(TokenError::NotWhitelisted)));
+}
+
+#[test]
+fn distributor_swap_goes_through_timelock() {
+    let t = setup();
+    let rogue = Address::generate(&t.env);
+    assert_eq!(t.client.try_set_distributor(&rogue), Err(Ok(TokenError::Timelocked)));
+    assert_eq!(t.client.try_schedule(&TimelockOp::SetDistributor(rogue.clone())), Err(Ok(TokenError::NoTimelock)));
+
+    let guardian = Address::generate(&t.env);
+    assert_eq!(t.client.try_init_timelock(&guardian, &0), Err(Ok(TokenError::DelayTooShort)));
+    t.client.init_timelock(&guardian, &MIN_DELAY);
+    let id = t.client.schedule(&TimelockOp::SetDistributor(rogue.clone()));
+    assert_eq!(t.client.try_execute(&id), Err(Ok(TokenError::NotReady)));
+    assert_eq!(t.client.distributor(), Some(t.distributor.clone()));
+    t.env.ledger().with_mut(|l| l.sequence_number += MIN_DELAY);
+    t.client.execute(&id);
+    assert_eq!(t.client.distributor(), Some(rogue));
+    assert_eq!(t.client.try_schedule(&TimelockOp::SetDelay(0)), Err(Ok(TokenError::DelayTooShort)));
+}
+
+#[test]
+fn whitelist_minter_and_guard_changes_are_timelocked() {
+    let t = setup();
+    let (a, minter, guard) = (investor(&t), Address::generate(&t.env), Address::generate(&t.env));
+    t.client.init_timelock(&Address::generate(&t.env), &MIN_DELAY);
+    assert_eq!(t.client.try_whitelist_remove(&a), Err(Ok(TokenError::Timelocked)));
+    assert_eq!(t.client.try_set_minter(&minter, &true), Err(Ok(TokenError::Timelocked)));
+    assert_eq!(t.client.try_set_mint_guard(&guard), Err(Ok(TokenError::Timelocked)));
+
+    let ids = [
+        t.client.schedule(&TimelockOp::WhitelistRemove(soroban_sdk::vec![&t.env, a.clone()])),
+        t.client.schedule(&TimelockOp::SetMinter(minter.clone(), true)),
+        t.client.schedule(&TimelockOp::SetMintGuard(guard)),
+    ];
+    t.env.ledger().with_mut(|l| l.sequence_number += MIN_DELAY);
+    for id in ids { t.client.execute(&id); }
+    assert!(!t.client.is_whitelisted(&a));
+    assert!(t.client.is_minter(&minter));
+}
+
+#[test]
+fn expire_reward_only_takes_what_accrued_before_the_mark() {
+    let t = setup();
+    let (a, b) = (investor(&t), investor(&t));
+    t.client.mint(&a, &1);
+    t.client.mint(&b, &1);
+    t.client.add_reward(&10);
+    let mark = t.client.reward_per_share();
+    t.client.add_reward(&4);
+
+    assert_eq!(t.client.expire_reward(&a, &mark), 5);
+    assert_eq!(t.client.pending_reward(&a), 2);
+    assert_eq!(t.client.expire_reward(&a, &mark), 0);
+
+    // b movimentou cotas depois do corte: nada prescreve
+    t.client.transfer(&b, &a, &1);
+    assert_eq!(t.client.expire_reward(&b, &mark), 0);
+    assert_eq!(t.client.pending_reward(&b), 7);
+    assert_eq!(t.client.try_expire_reward(&a, &(t.client.reward_per_share() + 1)), Err(Ok(TokenError::InvalidAmount)));
 }
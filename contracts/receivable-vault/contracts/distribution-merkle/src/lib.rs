@@ -0,0 +1,108 @@
+//! Árvore de Merkle das distribuições do `ReceivableVault` (`publish_merkle_root` / `claim_merkle`).
+//!
+//! Monta a árvore a partir de um snapshot `(cotista, valor)` e gera as provas de cada cotista,
+//! com o mesmo encoding que o contrato verifica:
+//! - folha: `sha256(0x00 || XDR do ScVal::Address || valor i128 big-endian)`
+//! - nó:    `sha256(0x01 || menor || maior)` (pares ordenados, a prova não leva posição)
+//!
+//! Nível com quantidade ímpar de nós: o último sobe sem ser combinado.
+//! Cada cotista aparece uma única vez: o contrato marca o saque por endereço, então uma segunda
+//! folha do mesmo cotista ficaria presa no vault.
+
+use std::collections::HashSet;
+
+use sha2::{Digest, Sha256};
+use stellar_strkey::Strkey;
+use stellar_xdr::curr::{AccountId, Hash as XdrHash, Limits, PublicKey, ScAddress, ScVal, Uint256, WriteXdr};
+
+pub type Hash = [u8; 32];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    Empty,
+    DuplicateHolder,
+    InvalidAddress,
+    Xdr,
+}
+
+pub fn leaf(holder: &ScAddress, amount: i128) -> Result<Hash, Error> {
+    let xdr = ScVal::Address(holder.clone()).to_xdr(Limits::none()).map_err(|_| Error::Xdr)?;
+    let mut h = Sha256::new();
+    h.update([0u8]);
+    h.update(&xdr);
+    h.update(amount.to_be_bytes());
+    Ok(h.finalize().into())
+}
+
+pub fn node(a: &Hash, b: &Hash) -> Hash {
+    let (l, r) = if a <= b { (a, b) } else { (b, a) };
+    let mut h = Sha256::new();
+    h.update([1u8]);
+    h.update(l);
+    h.update(r);
+    h.finalize().into()
+}
+
+/// Converte um endereço strkey (`G...` conta, `C...` contrato) em `ScAddress`.
+pub fn parse_address(strkey: &str) -> Result<ScAddress, Error> {
+    match Strkey::from_string(strkey).map_err(|_| Error::InvalidAddress)? {
+        Strkey::PublicKeyEd25519(pk) => Ok(ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(pk.0))))),
+        Strkey::Contract(c) => Ok(ScAddress::Contract(XdrHash(c.0))),
+        _ => Err(Error::InvalidAddress),
+    }
+}
+
+/// Confere uma prova gerada por `MerkleTree::proof` (mesma lógica de `claim_merkle`).
+pub fn verify(root: &Hash, leaf: &Hash, proof: &[Hash]) -> bool {
+    proof.iter().fold(*leaf, |acc, p| node(&acc, p)) == *root
+}
+
+pub struct MerkleTree {
+    layers: Vec<Vec<Hash>>,     // layers[0] = folhas, na ordem do snapshot; última = [raiz]
+}
+
+impl MerkleTree {
+    pub fn from_snapshot(entries: &[(ScAddress, i128)]) -> Result<Self, Error> {
+        if entries.is_empty() { return Err(Error::Empty); }
+        let mut seen = HashSet::new();
+        if !entries.iter().all(|(h, _)| seen.insert(h)) { return Err(Error::DuplicateHolder); }
+        let leaves = entries.iter().map(|(h, a)| leaf(h, *a)).collect::<Result<Vec<_>, _>>()?;
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers.last().unwrap()
+                .chunks(2)
+                .map(|c| if c.len() == 2 { node(&c[0], &c[1]) } else { c[0] })
+                .collect();
+            layers.push(next);
+        }
+        Ok(MerkleTree { layers })
+    }
+
+    /// Snapshot com endereços em strkey.
+    pub fn from_strkeys(entries: &[(&str, i128)]) -> Result<Self, Error> {
+        let parsed = entries.iter().map(|(s, a)| Ok((parse_address(s)?, *a))).collect::<Result<Vec<_>, Error>>()?;
+        Self::from_snapshot(&parsed)
+    }
+
+    pub fn root(&self) -> Hash { self.layers.last().unwrap()[0] }
+
+    pub fn len(&self) -> usize { self.layers[0].len() }
+
+    pub fn is_empty(&self) -> bool { self.layers[0].is_empty() }
+
+    /// Prova da folha na posição `index` do snapshot (irmãos da folha até a raiz).
+    pub fn proof(&self, index: usize) -> Option<Vec<Hash>> {
+        if index >= self.len() { return None; }
+        let mut proof = Vec::new();
+        let mut i = index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            let sibling = i ^ 1;
+            if sibling < layer.len() { proof.push(layer[sibling]); }
+            i /= 2;
+        }
+        Some(proof)
+    }
+}
+
+#[cfg(test)]
+mod test;
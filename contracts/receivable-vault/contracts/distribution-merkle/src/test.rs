@@ -0,0 +1,44 @@
+use super::*;
+
+fn account(n: u8) -> ScAddress {
+    ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256([n; 32]))))
+}
+
+#[test]
+fn every_proof_verifies_against_the_root() {
+    for size in 1..=9u8 {
+        let entries: Vec<_> = (0..size).map(|i| (account(i), 100 + i as i128)).collect();
+        let tree = MerkleTree::from_snapshot(&entries).unwrap();
+        for (i, (h, a)) in entries.iter().enumerate() {
+            let proof = tree.proof(i).unwrap();
+            assert!(verify(&tree.root(), &leaf(h, *a).unwrap(), &proof));
+        }
+        assert!(tree.proof(size as usize).is_none());
+    }
+}
+
+#[test]
+fn wrong_amount_or_holder_fails() {
+    let entries = [(account(1), 10), (account(2), 20), (account(3), 30)];
+    let tree = MerkleTree::from_snapshot(&entries).unwrap();
+    let proof = tree.proof(1).unwrap();
+    assert!(!verify(&tree.root(), &leaf(&account(2), 21).unwrap(), &proof));
+    assert!(!verify(&tree.root(), &leaf(&account(3), 20).unwrap(), &proof));
+}
+
+#[test]
+fn duplicate_holders_are_rejected() {
+    let entries = [(account(1), 10), (account(2), 20), (account(1), 30)];
+    assert_eq!(MerkleTree::from_snapshot(&entries).err(), Some(Error::DuplicateHolder));
+    let g = "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF";
+    assert_eq!(MerkleTree::from_strkeys(&[(g, 1), (g, 2)]).err(), Some(Error::DuplicateHolder));
+}
+
+#[test]
+fn parses_account_and_contract_strkeys() {
+    let g = "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF";
+    assert_eq!(parse_address(g).unwrap(), account(0));
+    assert!(matches!(parse_address("CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABSC4"), Ok(ScAddress::Contract(_))));
+    assert_eq!(parse_address("nope"), Err(Error::InvalidAddress));
+    assert!(MerkleTree::from_strkeys(&[]).is_err());
+}
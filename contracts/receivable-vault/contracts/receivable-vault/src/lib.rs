@@ -1,12 +1,253 @@
 #![no_std]
 use soroban_sdk::{
-    contract, contractimpl, contracterror, contracttype,
-    Address, Env, String, Vec, vec, symbol_short,
+    contract, contractclient, contractimpl, contracterror, contracttype, token, xdr::ToXdr,
+    Address, Bytes, BytesN, Env, Map, String, Symbol, Vec, vec, symbol_short,
 };
 
+const MAX_DOCUMENTS: u32 = 16;   // limite de documentos ancorados por registro
+const MAX_INSTALLMENTS: u32 = 120; // limite de parcelas por recebível
+// Registro: cada item grava Receivable e FundReceivable(fundo, pos); FundReceivableCount,
+// Production(consultor, fundo) e a instância são compartilhados pelo lote. Baixa: cada item
+// grava o Receivable; as entradas de comissão são por consultor/fundo. O gargalo é o limite de
+// 25 escritas por transação (2 por item no registro); os benchmarks em test.rs conferem
+// entradas, bytes e CPU.
+pub const MAX_BATCH: u32 = 10;
+// Candidatos ao resíduo guardados numa distribuição paginada (ver DistributionRun).
+const RESIDUE_CANDIDATES: u32 = 64;
+const MAX_BUCKETS: u32 = 10;     // faixas por cascata de pagamentos
+const MAX_ESCROW_LOTS: u32 = 16; // retenções com prazo próprio por cotista; acima disso, junta na última
+// Pedidos de resgate em fila por fundo. A fila inteira cabe numa entrada; a liquidação é paginada.
+pub const MAX_REDEMPTIONS: u32 = 50;
+// Liquidação de resgates: cada pedido pago escreve o Redemption e o saldo do cotista no ativo de
+// pagamento, sobre ~9 escritas fixas (fila, caixa, gate, Claimable, saldos do vault), dentro do
+// limite de 25 escritas por transação.
+pub const MAX_SETTLE_PAGE: u32 = 6;
+// Marcação do NAV: até 4 leituras por título (posição, Receivable, Acquisition, Installments)
+// sobre as ~12 fixas da chamada, dentro do limite de 40 entradas lidas por transação.
+pub const MAX_NAV_PAGE: u32 = 6;
+pub const MIN_DELAY: u32 = 17_280;  // atraso mínimo do timelock, em ledgers (~1 dia a 5 s por ledger)
+const NAV_MAX_AGE: u64 = DAY;      // subscrição e resgate só usam NAV marcado há menos de um dia
+const BPS: i128 = 10_000;
+const YEAR: u64 = 365 * 24 * 60 * 60;
+const DAY: u64 = 24 * 60 * 60;
+const RATE_SCALE: i128 = 1_000_000_000_000; // taxa diária da curva de aquisição (1e12 = 100% a.d.)
+pub const PRICE_SCALE: i128 = 10_000_000;     // preço por cota inteira (FundToken tem 7 decimais)
+const REWARD_PRECISION: i128 = 1_000_000_000_000_000_000; // escala do RewardPerShare do FundToken
+
+/// Interface do contrato FundToken usada pelo vault.
+#[contractclient(name = "FundTokenClient")]
+pub trait FundTokenInterface {
+    fn balance(env: Env, addr: Address) -> i128;
+    fn total_supply(env: Env) -> i128;
+    fn is_paused(env: Env) -> bool;
+    fn is_whitelisted(env: Env, address: Address) -> bool;
+    fn add_reward(env: Env, amount: i128);
+    fn take_reward(env: Env, holder: Address) -> i128;
+    fn pending_reward(env: Env, holder: Address) -> i128;
+    fn reward_per_share(env: Env) -> i128;
+    fn expire_reward(env: Env, holder: Address, mark: i128) -> i128;
+    fn max_supply(env: Env) -> i128;
+    fn mint_by(env: Env, minter: Address, to: Address, amount: i128);
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) -> bool;
+    fn burn(env: Env, from: Address, amount: i128);
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub enum Status { Pending = 0, Approved = 1, Rejected = 2, Suspended = 3 }
+
+#[derive(Clone)]
+#[contracttype]
+pub enum EntityKind { Cedente = 0, Sacado = 1, Fund = 2 }
+
+/// Conjunto de aprovadores de um fundo: ações sensíveis exigem `threshold` assinaturas distintas.
+#[derive(Clone)]
+#[contracttype]
+pub struct ApproverSet {
+    pub approvers: Vec<Address>,
+    pub threshold: u32,         // M
+    pub ttl: u64,               // validade de uma proposta, em segundos
+    pub receivable_limit: i128, // registro acima deste valor de face exige proposta
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub enum ProposalAction {
+    ApproveEntity(EntityKind, String, bool, Option<String>),
+    RegisterReceivable(ReceivableInput),
+    Distribute(String, Vec<Address>),
+    DistributeClaimable(String, Address),
+    StartDistribution(String, Vec<Address>),
+    DistributeInstallment(String, u32, Vec<Address>),
+    PublishMerkleRoot(MerklePublication), // `from` assina a transação que atinge o quórum
+    SetApprovers(String, Vec<Address>, u32, u64, i128),
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct Proposal {
+    pub id: u32,
+    pub fund_id: String,
+    pub action: ProposalAction,
+    pub approvals: Vec<Address>, // aprovadores distintos, o proponente é o primeiro
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub executed: bool,
+}
+
+/// Tabela de PDD por faixa de atraso. `bounds[i]` é o último dia de atraso da faixa `i`
+/// (crescente); a última faixa, `bounds.len()`, cobre o que passar do último limite.
+/// `rates` tem uma taxa (bps) por faixa, portanto `bounds.len() + 1` entradas.
+#[derive(Clone)]
+#[contracttype]
+pub struct ProvisionTable {
+    pub bounds: Vec<u32>,
+    pub rates: Vec<u32>,
+}
+
+/// Situação de um recebível para a PDD.
+#[derive(Clone)]
+#[contracttype]
+pub struct Aging {
+    pub days_overdue: u32,      // da parcela em aberto mais antiga (efeito arrasto)
+    pub bucket: u32,
+    pub exposure: i128,         // valor em aberto, líquido de pagamentos
+    pub provision: i128,        // exposure * taxa da faixa / 10_000, arredondado para baixo
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct ProvisionReport {
+    pub exposure: Vec<i128>,    // por faixa
+    pub provision: Vec<i128>,   // por faixa: soma das provisões de cada recebível
+    pub total: i128,
+}
+
+/// Cessão de um recebível: o fundo pagou `price` e o título acumula à taxa diária até o vencimento.
+#[derive(Clone)]
+#[contracttype]
+pub struct Acquisition {
+    pub price: i128,
+    pub daily_rate: i128,       // escala RATE_SCALE, juros compostos por dia corrido
+    pub acquired_at: u64,
+}
+
+/// Composição do patrimônio líquido do fundo.
+#[derive(Clone)]
+#[contracttype]
+pub struct Nav {
+    pub receivables: i128,      // valor presente dos títulos em aberto
+    pub collections: i128,      // recebido e ainda não distribuído
+    pub cash: i128,
+    pub reserve: i128,
+    pub fees: i128,             // comissões acumuladas e ainda não provisionadas
+    pub provision: i128,        // PDD
+    pub nav: i128,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct NavEntry {
+    pub nav: i128,
+    pub supply: i128,
+    pub price: i128,            // por cota inteira, escala PRICE_SCALE
+    pub at: u64,
+}
+
+/// Marcação do NAV em páginas (`record_nav`): títulos somados até `cursor`, todos a valor de `at`.
+#[derive(Clone)]
+#[contracttype]
+pub struct NavRun {
+    pub at: u64,
+    pub cursor: u32,
+    pub value: Nav,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct SubscriptionTerms {
+    pub min_amount: i128,       // aporte mínimo, no ativo de pagamento
+    pub initial_price: i128,    // preço da cota enquanto o supply é zero (escala PRICE_SCALE)
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct RedemptionTerms {
+    pub notice: u64,            // prazo de cotização (ex.: D+30 = 30 * 86_400)
+    pub gate_bps: u32,          // máximo do NAV resgatável por período
+    pub period: u64,            // duração do período do gate, em segundos
+    pub min_quotas: i128,       // pedido mínimo: a fila tem MAX_REDEMPTIONS posições
+}
+
+/// Pedido de resgate. As cotas ficam travadas no vault até a liquidação.
+#[derive(Clone)]
+#[contracttype]
+pub struct Redemption {
+    pub id: u32,
+    pub fund_id: String,
+    pub investor: Address,
+    pub quotas: i128,           // ainda não liquidadas
+    pub requested_at: u64,
+    pub eligible_at: u64,
+    pub reward_mark: i128,      // RewardPerShare até onde o rendimento das cotas travadas foi repassado
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct GateWindow {
+    pub start: u64,
+    pub limit: i128,            // gate_bps do NAV no início do período
+    pub used: i128,
+}
+
+/// Liquidação de resgates em páginas (`settle_redemptions`), toda ao instante, preço e
+/// RewardPerShare da primeira página. A 1ª passada soma a demanda elegível; a 2ª paga a fração
+/// `num / den` de cada pedido com o caixa separado em `reserved`.
+#[derive(Clone)]
+#[contracttype]
+pub struct SettleRun {
+    pub at: u64,
+    pub price: i128,
+    pub rps: i128,
+    pub taken: i128,            // rendimento das cotas travadas retirado do FundToken na abertura
+    pub count: u32,             // pedidos na fila; os que chegarem depois ficam para a próxima
+    pub cursor: u32,
+    pub paying: bool,
+    pub demand: i128,
+    pub gate: GateWindow,
+    pub num: i128,
+    pub den: i128,
+    pub reserved: i128,
+    pub paid: i128,
+    pub rewards: i128,
+}
+
+/// Operações de governança que só valem depois do atraso do timelock.
+#[derive(Clone)]
+#[contracttype]
+pub enum TimelockOp {
+    SetPaymentAsset(String, Address),
+    Upgrade(BytesN<32>),        // hash do novo WASM já instalado
+    SetDelay(u32),
+    SetGuardian(Address),
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct ScheduledOp {
+    pub op: TimelockOp,
+    pub eta: u32,               // ledger a partir do qual pode ser executada
+}
+
+/// Uma transição de status de um cadastro.
 #[derive(Clone)]
 #[contracttype]
-pub enum Status { Pending = 0, Approved = 1, Rejected = 2 }
+pub struct StatusChange {
+    pub status: Status,
+    pub by: Address,            // consultor (submissão) ou gestor (revisão)
+    pub at: u64,
+    pub reason: Option<String>, // obrigatório em rejeição e suspensão
+}
 
 #[derive(Clone)]
 #[contracttype]
@@ -14,21 +255,100 @@ pub enum DataKey {
     Admin,
     FundTokenAddress,           // endereço do contrato de cotas deste fundo
     Consultants(Address),       // whitelist de consultores
+    ConsultantFund(Address, String), // consultor designado para o fundo
+    Production(Address, String),     // (consultor, fund_id) -> Production
+    CommissionRate(String),     // fund_id -> comissão padrão do fundo, em bps
+    ConsultantRate(Address),    // consultor -> comissão própria (prevalece sobre a do fundo), em bps
+    CommissionDue(Address, String), // (consultor, fund_id) -> comissão acumulada e não sacada
+    CommissionAccrued(Address), // consultor -> total acumulado e não sacado, em todos os fundos
+    CommissionUnfunded(String), // fund_id -> comissão acumulada ainda não provisionada pela cascata
+    CommissionPool(String),     // fund_id -> comissão provisionada, disponível para saque
+    CommissionFunded(Address, String), // (consultor, fund_id) -> parte de CommissionDue já provisionada
+    CommissionBacked(String),   // fund_id -> comissão depositada no vault (fund_commission) e ainda não sacada
     Cedente(String),            // id -> EntityRecord
     Sacado(String),
     Fund(String),               // fund_id -> FundRecord
+    StatusHistory(EntityKind, String), // histórico de status do cadastro (Vec<StatusChange>)
+    Approvers(String),          // fund_id -> ApproverSet (aprovação M-de-N)
+    Proposal(u32),              // id -> Proposal
+    ProposalCount,
     Receivable(String),         // id -> Receivable
+    Installments(String),       // id -> Vec<Installment> (recebíveis parcelados)
+    DistRun(String),            // receivable_id -> DistributionRun em andamento
+    DistHolder(String, u32),    // (receivable_id, posição) -> Address do cotista
+    DistSeen(String, Address),  // cotista já incluído na distribuição (evita duplicata)
+    DistClear(String),          // receivable_id -> cotistas da execução encerrada ainda a apagar
+    HolderPolicy(String),       // fund_id -> HolderPolicy
+    Escrow(String, Address),    // (fund_id, cotista) -> UnclaimedBalance retido
+    EscrowHolders(String),      // fund_id -> cotistas com valor retido, em ordem de retenção
+    ClaimWindow(String),        // fund_id -> prazo (segundos) para receber o valor retido
+    Reserve(String),            // fund_id -> saldo da reserva do fundo
+    ReserveBacked(String),      // fund_id -> parte da reserva coberta por ativo depositado no vault
+    ReserveConfig(String),      // fund_id -> ReserveConfig (meta da reserva)
+    Waterfall(String),          // fund_id -> Vec<WaterfallBucket>, em ordem de prioridade
+    QuotaClasses(String),       // fund_id -> Vec<QuotaClass>, da mais sênior à subordinada
+    ClassToken(Address),        // FundToken de classe -> fund_id
+    MinSubordination(String),   // fund_id -> razão mínima subordinada / PL, em bps
+    Unallocated(String),        // fund_id -> valores não pagos (política Skip)
+    PaymentAsset(String),       // fund_id -> token (SAC) em que os rendimentos são pagos
+    Claimable(String),          // fund_id -> total lançado no acumulador e ainda não sacado
+    RewardMark(Address, u32),   // (FundToken, n) -> (timestamp, RewardPerShare) após o n-ésimo lançamento
+    RewardMarkCount(Address),
+    MerkleDist(String),         // dist_id -> MerkleDistribution
+    MerkleClaimed(String, Address), // (dist_id, cotista) -> já sacou
+    FundReceivableCount(String),     // fund_id -> nº de recebíveis registrados no fundo
+    FundReceivable(String, u32),     // (fund_id, posição) -> id do recebível
+    ProvisionTable(String),     // fund_id -> ProvisionTable (PDD)
+    Acquisition(String),        // receivable_id -> Acquisition (curva de aquisição)
+    Cash(String),               // fund_id -> caixa do fundo no vault (aportes ainda não aplicados)
+    NavCount(String),           // fund_id -> nº de registros de NAV
+    NavHistory(String, u32),    // (fund_id, posição) -> NavEntry
+    NavRun(String),             // fund_id -> marcação do NAV em andamento
+    SubscriptionTerms(String),  // fund_id -> SubscriptionTerms
+    RedemptionTerms(String),    // fund_id -> RedemptionTerms
+    Redemption(u32),            // id -> Redemption
+    RedemptionQueue(String),    // fund_id -> ids dos pedidos em aberto, em ordem de chegada
+    SettleRun(String),          // fund_id -> liquidação de resgates em andamento
+    RedemptionCount,
+    Gate(String),               // fund_id -> GateWindow do período corrente
+    Guardian,                   // pode cancelar operações agendadas no timelock
+    TimelockDelay,              // atraso mínimo, em ledgers, entre agendar e executar
+    TimelockOp(u32),            // op_id -> ScheduledOp
+    TimelockCount,
     ReceivableCount,
     TotalPaid,
 }
 
+#[derive(Clone)]
+#[contracttype]
+pub enum DocType { Invoice = 0, Assignment = 1, DeliveryProof = 2, KycDossier = 3 }
+
+#[derive(Clone)]
+#[contracttype]
+pub enum DocTarget { Receivable = 0, Cedente = 1, Sacado = 2 }
+
+#[derive(Clone)]
+#[contracttype]
+pub struct Document {
+    pub doc_type: DocType,
+    pub sha256: BytesN<32>,     // hash do PDF no data room
+    pub uri: String,            // onde o documento pode ser obtido
+    pub added_by: Address,
+    pub added_at: u64,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub struct EntityRecord {
     pub id: String,             // CNPJ/CPF ou identificador
     pub name: String,
+    pub fund_id: String,        // fundo ao qual o cedente/sacado está vinculado
     pub created_by: Address,    // Consultor que propôs
     pub status: Status,
+    pub approved_by: Option<Address>, // gestor que aprovou
+    pub approved_at: u64,
+    pub rejection_reason: Option<String>,
+    pub documents: Vec<Document>, // dossiê KYC, contrato de cessão
 }
 
 #[derive(Clone)]
@@ -38,12 +358,125 @@ pub struct FundRecord {
     pub name: String,
     pub created_by: Address,    // Consultor que propôs
     pub status: Status,
+    pub approved_by: Option<Address>,
+    pub approved_at: u64,
+    pub rejection_reason: Option<String>,
     pub token: Address,         // endereço do FundToken (pode ficar zerado até aprovação)
 }
 
 #[derive(Clone)]
 #[contracttype]
-pub enum ReceivableStatus { Pending = 0, Paid = 1, Distributed = 2 }
+pub enum ReceivableStatus { Pending = 0, Paid = 1, Distributed = 2, Distributing = 3, WrittenOff = 4 }
+
+/// O que fazer com a parte de um cotista que não está (mais) na whitelist do FundToken.
+#[derive(Clone)]
+#[contracttype]
+pub enum HolderPolicy {
+    Escrow = 0,   // retém no vault até o cotista voltar à whitelist (padrão)
+    Skip = 1,     // não paga; o valor fica como não alocado no fundo
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub enum BucketKind {
+    Administration = 0, // taxa de administração
+    Custody = 1,        // taxa de custódia
+    Management = 2,     // taxa de gestão
+    Reserve = 3,        // recompõe a reserva do fundo (o recipient é ignorado)
+    Commission = 4,     // provisiona a comissão do consultor do título pago, até `amount` por pagamento
+                        // (o recipient é ignorado; o saque é via claim_commission)
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub enum BucketAmount {
+    Bps(u32),           // percentual do valor recebido, em pontos-base
+    Fixed(i128),        // valor fixo por pagamento
+}
+
+/// Faixa da cascata de pagamentos, paga antes dos cotistas. Cada faixa leva o seu valor
+/// (percentual sobre o valor recebido bruto, ou fixo) limitado ao que sobrou das anteriores.
+#[derive(Clone)]
+#[contracttype]
+pub struct WaterfallBucket {
+    pub kind: BucketKind,
+    pub recipient: Address,
+    pub amount: BucketAmount,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[contracttype]
+pub enum QuotaClassKind { Senior = 0, Mezzanine = 1, Subordinated = 2 }
+
+/// Classe de cotas do fundo, cada uma com o seu FundToken.
+#[derive(Clone)]
+#[contracttype]
+pub struct QuotaClass {
+    pub kind: QuotaClassKind,
+    pub token: Address,
+    pub benchmark_bps: u32,     // remuneração-alvo ao ano (sênior/mezanino); ignorada na subordinada
+    pub principal: i128,        // valor da classe, já líquido das perdas absorvidas
+    pub losses: i128,           // perdas absorvidas pela classe
+    pub shortfall: i128,        // remuneração-alvo devida e ainda não paga
+    pub last_accrual: u64,
+}
+
+/// Base sobre a qual a meta da reserva de liquidez é calculada.
+#[derive(Clone)]
+#[contracttype]
+pub enum ReserveBase {
+    Expenses(i128),     // despesas previstas do fundo, informadas pelo gestor
+    QuotaValue,         // valor das cotas em circulação (principal das classes ou NAV do fundo)
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct ReserveConfig {
+    pub base: ReserveBase,
+    pub bps: u32,       // meta = base * bps / 10_000
+}
+
+/// Valor retido no vault para um cotista, desde a primeira retenção ainda não paga.
+#[derive(Clone)]
+#[contracttype]
+pub struct UnclaimedBalance {
+    pub holder: Address,
+    pub amount: i128,           // soma dos lotes
+    pub since: u64,
+    pub expires_at: u64,        // prazo do lote que vence primeiro (u64::MAX se o fundo não tem prazo)
+    pub lots: Vec<EscrowLot>,
+}
+
+/// Uma retenção, com o prazo contado a partir dela.
+#[derive(Clone)]
+#[contracttype]
+pub struct EscrowLot {
+    pub amount: i128,
+    pub expires_at: u64,
+}
+
+/// Destino dos valores expirados.
+#[derive(Clone)]
+#[contracttype]
+pub enum SweepTarget {
+    Reserve,                // vai para a reserva do fundo
+    Redistribute(Address),  // volta aos cotistas atuais pelo acumulador; valores retidos são só
+                            // contábeis, então o endereço deposita o total no ato
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub enum ReceivableKind { Single = 0, Installments = 1 }
+
+#[derive(Clone)]
+#[contracttype]
+pub struct Installment {
+    pub due_date: u64,
+    pub amount: i128,
+    pub status: ReceivableStatus, // cada parcela é paga e distribuída separadamente
+    pub paid_amount: i128,
+    pub paid_date: u64,
+}
 
 #[derive(Clone)]
 #[contracttype]
@@ -52,12 +485,108 @@ pub struct Receivable {
     pub fund_id: String,
     pub cedente_id: String,
     pub sacado_id: String,
-    pub face_value: i128,
-    pub due_date: u64,
+    pub kind: ReceivableKind,
+    pub face_value: i128,        // parcelado: soma das parcelas
+    pub due_date: u64,           // parcelado: vencimento da última parcela
     pub debtor_address: Address, // opcional: sacado on-chain
     pub status: ReceivableStatus,
     pub paid_amount: i128,
     pub paid_date: u64,
+    pub consultant: Address,     // consultor que originou o cedente
+    pub documents: Vec<Document>, // nota fiscal, contrato de cessão, comprovante de entrega
+}
+
+/// Produção de um consultor em um fundo: recebíveis originados a partir dos seus cedentes.
+#[derive(Clone, Default)]
+#[contracttype]
+pub struct Production {
+    pub count: u32,
+    pub face_value: i128,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct ReceivableInput {
+    pub id: String,
+    pub fund_id: String,
+    pub cedente_id: String,
+    pub sacado_id: String,
+    pub face_value: i128,
+    pub due_date: u64,
+    pub debtor_address: Address,
+}
+
+/// Distribuição paginada: valor e supply fixados em `start_distribution`, pagamentos em lotes.
+#[derive(Clone)]
+#[contracttype]
+pub struct DistributionRun {
+    pub receivable_id: String,
+    pub fund_id: String,
+    pub amount: i128,           // valor a distribuir (paid_amount líquido da cascata)
+    pub total_shares: i128,     // total_supply do FundToken no início (snapshot)
+    pub holder_count: u32,
+    pub cursor: u32,            // próxima posição a processar
+    pub distributed: i128,      // soma já paga
+    pub balance_sum: i128,      // soma dos saldos processados; deve fechar com total_shares
+    pub candidates: Vec<ResidueCandidate>, // maiores restos vistos, em ordem de prioridade
+    pub cutoff: Option<ResidueCandidate>,  // passada de resíduo: só entram candidatos abaixo deste
+    pub buckets: Vec<WaterfallBucket>,     // cascata paga no início, desfeita se a execução for cancelada
+    pub takes: Vec<i128>,
+    pub holdback: i128,
+}
+
+/// Cotista que pode receber um stroop do resíduo (maior resto) numa distribuição paginada.
+#[derive(Clone)]
+#[contracttype]
+pub struct ResidueCandidate {
+    pub holder: Address,
+    pub remainder: i128,        // (saldo * valor) % total_shares
+    pub balance: i128,
+    pub position: u32,
+}
+
+/// Distribuição publicada como raiz de Merkle de folhas (cotista, valor) calculadas off-chain
+/// (crate `distribution-merkle`). Cada cotista saca com a prova até `deadline`.
+#[derive(Clone)]
+#[contracttype]
+pub struct MerkleDistribution {
+    pub fund_id: String,
+    pub receivable_id: Option<String>, // None: distribuição de um período
+    pub root: BytesN<32>,
+    pub total: i128,
+    pub claimed: i128,
+    pub deadline: u64,          // depois disso não há saque e o saldo pode ser recolhido
+    pub swept: bool,
+}
+
+/// Argumentos de `publish_merkle_root`, para a proposta M-de-N.
+#[derive(Clone)]
+#[contracttype]
+pub struct MerklePublication {
+    pub from: Address,
+    pub dist_id: String,
+    pub fund_id: String,
+    pub receivable_id: Option<String>,
+    pub root: BytesN<32>,
+    pub total: i128,
+    pub deadline: u64,
+}
+
+/// Simulação de `distribute`: quanto cada cotista recebe e quem ficou com o resíduo.
+#[derive(Clone)]
+#[contracttype]
+pub struct DistributionPreview {
+    pub waterfall: Vec<(Address, i128)>, // valor de cada faixa da cascata, na ordem (+ retenção p/ reserva)
+    pub shares: Vec<(Address, i128)>,   // valor final por cotista (já com o resíduo)
+    pub residue: Vec<(Address, i128)>,  // stroops de resíduo atribuídos pelo maior resto
+}
+
+/// Resultado de operações em lote: tudo aplicado, ou nada aplicado e o primeiro item inválido.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum BatchResult {
+    Done(u32),          // quantidade de itens aplicados
+    Failed(u32, u32),   // (índice do item, código do VaultError)
 }
 
 #[contracterror]
@@ -71,11 +600,166 @@ pub enum VaultError {
     Invalid = 6,
     BadStatus = 7,
     Math = 8,
+    Unauthorized = 9,
+    TooManyDocuments = 10,
+    BatchTooLarge = 11,
+    NotPaused = 12,
+    SnapshotChanged = 13,
+    DuplicateHolder = 14,
+    IncompleteHolders = 15,
+    NotWhitelisted = 16,
+    NoPaymentAsset = 17,
+    AlreadyClaimed = 18,
+    InvalidProof = 19,
+    Expired = 20,
+    NotExpired = 21,
+    ClassedFund = 22,
+    NoClasses = 23,
+    InsufficientReserve = 24,
+    FundNotApproved = 25,
+    FundSuspended = 26,
+    CedenteNotApproved = 27,
+    CedenteSuspended = 28,
+    CedenteNotInFund = 29,
+    SacadoNotApproved = 30,
+    SacadoSuspended = 31,
+    SacadoNotInFund = 32,
+    NotAssigned = 33,
+    NeedsProposal = 34,
+    NotApprover = 35,
+    AlreadyApproved = 36,
+    NotReady = 37,
+    Timelocked = 38,
+    NoTimelock = 39,
+    NoSupply = 40,
+    BelowMinimum = 41,
+    MaxSupplyExceeded = 42,
+    QueueFull = 43,
+    StaleNav = 44,
+    DelayTooShort = 45,
+    InsufficientCash = 46,
 }
 
 fn add(a: i128, b: i128) -> Result<i128, VaultError> { a.checked_add(b).ok_or(VaultError::Math) }
 fn sub(a: i128, b: i128) -> Result<i128, VaultError> { a.checked_sub(b).ok_or(VaultError::Math) }
 
+fn zero_nav() -> Nav {
+    Nav { receivables: 0, collections: 0, cash: 0, reserve: 0, fees: 0, provision: 0, nav: 0 }
+}
+
+/// Valor presente de `face` a `days` dias, descontado à taxa diária composta. A potência é feita
+/// por quadrados sucessivos truncando a cada multiplicação; fatores fora da faixa de i128
+/// tornam o valor presente desprezível (0).
+fn discount(face: i128, daily_rate: i128, days: u64) -> Result<i128, VaultError> {
+    let mut factor = RATE_SCALE;
+    let mut base = RATE_SCALE + daily_rate;
+    let mut n = days;
+    while n > 0 {
+        if n & 1 == 1 {
+            factor = match factor.checked_mul(base) { Some(v) => v / RATE_SCALE, None => return Ok(0) };
+        }
+        n >>= 1;
+        if n > 0 {
+            base = match base.checked_mul(base) { Some(v) => v / RATE_SCALE, None => return Ok(0) };
+        }
+    }
+    Ok(face.checked_mul(RATE_SCALE).ok_or(VaultError::Math)? / factor)
+}
+
+// Folha: sha256(0x00 || XDR do ScVal::Address || valor i128 big-endian).
+fn merkle_leaf(env: &Env, holder: &Address, amount: i128) -> BytesN<32> {
+    let mut b = Bytes::new(env);
+    b.push_back(0);
+    b.append(&holder.clone().to_xdr(env));
+    b.extend_from_array(&amount.to_be_bytes());
+    env.crypto().sha256(&b).to_bytes()
+}
+// Nó interno: sha256(0x01 || menor || maior); pares ordenados dispensam a posição na prova.
+fn merkle_node(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+    let (l, r) = if a <= b { (a, b) } else { (b, a) };
+    let mut buf = Bytes::new(env);
+    buf.push_back(1);
+    buf.append(&Bytes::from(l.clone()));
+    buf.append(&Bytes::from(r.clone()));
+    env.crypto().sha256(&buf).to_bytes()
+}
+
+/// Ordem do rateio do resíduo: maior resto fracionário primeiro; empate -> maior saldo;
+/// persistindo o empate -> menor posição na lista de cotistas.
+fn ranks_before(rem_a: i128, bal_a: i128, pos_a: u32, rem_b: i128, bal_b: i128, pos_b: u32) -> bool {
+    (rem_a, bal_a) > (rem_b, bal_b) || ((rem_a, bal_a) == (rem_b, bal_b) && pos_a < pos_b)
+}
+
+/// Rateio pelo maior resto (Hamilton). Cada saldo recebe floor(saldo * amount / total) e o
+/// resíduo é entregue 1 stroop por vez na ordem de `ranks_before`. A soma das partes é sempre
+/// exatamente `amount`. Retorna (partes finais, stroops de resíduo recebidos por posição).
+fn allocate(env: &Env, balances: &Vec<i128>, amount: i128) -> Result<(Vec<i128>, Vec<i128>), VaultError> {
+    if amount < 0 { return Err(VaultError::Invalid); }
+    let mut total: i128 = 0;
+    for b in balances.iter() {
+        if b < 0 { return Err(VaultError::Invalid); }
+        total = add(total, b)?;
+    }
+    if total <= 0 { return Err(VaultError::Invalid); }
+
+    let mut shares: Vec<i128> = vec![env];
+    let mut extra: Vec<i128> = vec![env];
+    let mut ranked: Vec<(i128, i128, u32)> = vec![env]; // (resto, saldo, posição) com resto > 0
+    let mut floor_sum: i128 = 0;
+    for (i, b) in balances.iter().enumerate() {
+        let prod = b.checked_mul(amount).ok_or(VaultError::Math)?;
+        shares.push_back(prod / total);
+        extra.push_back(0);
+        if prod % total > 0 { ranked.push_back((prod % total, b, i as u32)); }
+        floor_sum = add(floor_sum, prod / total)?;
+    }
+
+    // resíduo < número de saldos com resto > 0, então cada um recebe no máximo 1 stroop:
+    // basta ordenar uma vez e dar 1 stroop aos `residue` primeiros
+    let residue = sub(amount, floor_sum)?;
+    if residue > ranked.len() as i128 { return Err(VaultError::Math); }
+    let ranked = sort_by_rank(env, ranked);
+    for k in 0..residue as u32 {
+        let (_, _, j) = ranked.get_unchecked(k);
+        extra.set(j, 1);
+        shares.set(j, shares.get_unchecked(j) + 1);
+    }
+    Ok((shares, extra))
+}
+
+/// Merge sort (bottom-up) de (resto, saldo, posição) na ordem de `ranks_before`: O(n log n).
+fn sort_by_rank(env: &Env, items: Vec<(i128, i128, u32)>) -> Vec<(i128, i128, u32)> {
+    let n = items.len();
+    let mut src = items;
+    let mut width: u32 = 1;
+    while width < n {
+        let mut dst: Vec<(i128, i128, u32)> = vec![env];
+        let mut lo: u32 = 0;
+        while lo < n {
+            let mid = core::cmp::min(lo + width, n);
+            let hi = core::cmp::min(lo.saturating_add(width.saturating_mul(2)), n);
+            let (mut i, mut j) = (lo, mid);
+            while i < mid && j < hi {
+                let (a, b) = (src.get_unchecked(i), src.get_unchecked(j));
+                if ranks_before(b.0, b.1, b.2, a.0, a.1, a.2) { dst.push_back(b); j += 1; } else { dst.push_back(a); i += 1; }
+            }
+            for k in i..mid { dst.push_back(src.get_unchecked(k)); }
+            for k in j..hi { dst.push_back(src.get_unchecked(k)); }
+            lo = hi;
+        }
+        src = dst;
+        width = width.saturating_mul(2);
+    }
+    src
+}
+
+/// Remuneração-alvo da classe desde `last_accrual` (zero na subordinada).
+fn benchmark_accrued(c: &QuotaClass, now: u64) -> Result<i128, VaultError> {
+    if let QuotaClassKind::Subordinated = c.kind { return Ok(0); }
+    let elapsed = now.saturating_sub(c.last_accrual) as i128;
+    Ok(c.principal.checked_mul(c.benchmark_bps as i128).and_then(|v| v.checked_mul(elapsed)).ok_or(VaultError::Math)? / (BPS * YEAR as i128))
+}
+
 #[contract]
 pub struct ReceivableVault;
 
@@ -96,150 +780,579 @@ impl ReceivableVault {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
         env.storage().persistent().set(&DataKey::Consultants(addr.clone()), &true);
-        env.events().publish((symbol_short!("consultant_add"),), addr);
+        env.events().publish((symbol_short!("cons_add"),), addr);
         Ok(())
     }
     pub fn consultant_remove(env: Env, addr: Address) -> Result<(), VaultError> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
         env.storage().persistent().remove(&DataKey::Consultants(addr.clone()));
-        env.events().publish((symbol_short!("consultant_remove"),), addr);
+        env.events().publish((symbol_short!("cons_rm"),), addr);
         Ok(())
     }
     fn is_consultant(env: &Env, addr: &Address) -> bool {
         env.storage().persistent().get(&DataKey::Consultants(addr.clone())).unwrap_or(false)
     }
 
+    /// Designa o consultor para o fundo: só então ele pode propor cedentes e sacados nele.
+    pub fn consultant_assign(env: Env, addr: Address, fund_id: String) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if !Self::is_consultant(&env, &addr) { return Err(VaultError::NotConsultant); }
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        env.storage().persistent().set(&DataKey::ConsultantFund(addr.clone(), fund_id.clone()), &true);
+        env.events().publish((symbol_short!("cons_asgn"), fund_id), addr);
+        Ok(())
+    }
+    pub fn consultant_unassign(env: Env, addr: Address, fund_id: String) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        env.storage().persistent().remove(&DataKey::ConsultantFund(addr.clone(), fund_id.clone()));
+        env.events().publish((symbol_short!("cons_unas"), fund_id), addr);
+        Ok(())
+    }
+    pub fn is_assigned(env: Env, addr: Address, fund_id: String) -> bool {
+        env.storage().persistent().get(&DataKey::ConsultantFund(addr, fund_id)).unwrap_or(false)
+    }
+
     // -------- Cadastros propostos pelo Consultor --------
-    pub fn submit_cedente(env: Env, id: String, name: String) -> Result<(), VaultError> {
-        let who = env.invoker();
-        if !Self::is_consultant(&env, &who) { return Err(VaultError::NotConsultant); }
+    pub fn submit_cedente(env: Env, consultant: Address, id: String, name: String, fund_id: String) -> Result<(), VaultError> {
+        consultant.require_auth();
+        if !Self::is_consultant(&env, &consultant) { return Err(VaultError::NotConsultant); }
         if id.len() == 0 || name.len() == 0 { return Err(VaultError::Invalid); }
         if env.storage().persistent().has(&DataKey::Cedente(id.clone())) { return Err(VaultError::DuplicateId); }
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        if !Self::is_assigned(env.clone(), consultant.clone(), fund_id.clone()) { return Err(VaultError::NotAssigned); }
 
-        let rec = EntityRecord { id: id.clone(), name, created_by: who.clone(), status: Status::Pending };
+        let rec = EntityRecord {
+            id: id.clone(), name, fund_id, created_by: consultant.clone(), status: Status::Pending,
+            approved_by: None, approved_at: 0, rejection_reason: None, documents: Vec::new(&env),
+        };
         env.storage().persistent().set(&DataKey::Cedente(id.clone()), &rec);
-        env.events().publish((symbol_short!("entity_submit"), symbol_short!("cedente"), id), 0i128);
+        Self::push_history(&env, EntityKind::Cedente, &id, Status::Pending, &consultant, None);
+        env.events().publish((symbol_short!("ent_sub"), symbol_short!("cedente"), id), 0i128);
         Ok(())
     }
 
-    pub fn submit_sacado(env: Env, id: String, name: String) -> Result<(), VaultError> {
-        let who = env.invoker();
-        if !Self::is_consultant(&env, &who) { return Err(VaultError::NotConsultant); }
+    pub fn submit_sacado(env: Env, consultant: Address, id: String, name: String, fund_id: String) -> Result<(), VaultError> {
+        consultant.require_auth();
+        if !Self::is_consultant(&env, &consultant) { return Err(VaultError::NotConsultant); }
         if id.len() == 0 || name.len() == 0 { return Err(VaultError::Invalid); }
         if env.storage().persistent().has(&DataKey::Sacado(id.clone())) { return Err(VaultError::DuplicateId); }
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        if !Self::is_assigned(env.clone(), consultant.clone(), fund_id.clone()) { return Err(VaultError::NotAssigned); }
 
-        let rec = EntityRecord { id: id.clone(), name, created_by: who.clone(), status: Status::Pending };
+        let rec = EntityRecord {
+            id: id.clone(), name, fund_id, created_by: consultant.clone(), status: Status::Pending,
+            approved_by: None, approved_at: 0, rejection_reason: None, documents: Vec::new(&env),
+        };
         env.storage().persistent().set(&DataKey::Sacado(id.clone()), &rec);
-        env.events().publish((symbol_short!("entity_submit"), symbol_short!("sacado"), id), 0i128);
+        Self::push_history(&env, EntityKind::Sacado, &id, Status::Pending, &consultant, None);
+        env.events().publish((symbol_short!("ent_sub"), symbol_short!("sacado"), id), 0i128);
         Ok(())
     }
 
-    pub fn submit_fund(env: Env, id: String, name: String, token_addr: Address) -> Result<(), VaultError> {
-        let who = env.invoker();
-        if !Self::is_consultant(&env, &who) { return Err(VaultError::NotConsultant); }
+    pub fn submit_fund(env: Env, consultant: Address, id: String, name: String, token_addr: Address) -> Result<(), VaultError> {
+        consultant.require_auth();
+        if !Self::is_consultant(&env, &consultant) { return Err(VaultError::NotConsultant); }
         if id.len() == 0 || name.len() == 0 { return Err(VaultError::Invalid); }
         if env.storage().persistent().has(&DataKey::Fund(id.clone())) { return Err(VaultError::DuplicateId); }
 
-        let rec = FundRecord { id: id.clone(), name, created_by: who.clone(), status: Status::Pending, token: token_addr };
+        let rec = FundRecord {
+            id: id.clone(), name, created_by: consultant.clone(), status: Status::Pending,
+            approved_by: None, approved_at: 0, rejection_reason: None, token: token_addr,
+        };
         env.storage().persistent().set(&DataKey::Fund(id.clone()), &rec);
-        env.events().publish((symbol_short!("entity_submit"), symbol_short!("fund"), id), 0i128);
+        // quem propõe o fundo já fica designado para ele
+        env.storage().persistent().set(&DataKey::ConsultantFund(consultant.clone(), id.clone()), &true);
+        Self::push_history(&env, EntityKind::Fund, &id, Status::Pending, &consultant, None);
+        env.events().publish((symbol_short!("ent_sub"), symbol_short!("fund"), id), 0i128);
         Ok(())
     }
 
     // -------- Aprovação pelo Gestor --------
-    pub fn approve_entity(env: Env, kind: String, id: String, approve: bool) -> Result<(), VaultError> {
+    /// Aprova ou rejeita um cadastro pendente. Rejeição exige motivo.
+    /// Fundos com aprovadores configurados exigem proposta (ver `propose`).
+    pub fn approve_entity(env: Env, kind: EntityKind, id: String, approve: bool, reason: Option<String>) -> Result<(), VaultError> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
-        let st = if approve { Status::Approved } else { Status::Rejected };
+        if Self::needs_proposal(&env, &Self::entity_fund(&env, &kind, &id)?, None) { return Err(VaultError::NeedsProposal); }
+        Self::review_entity(&env, kind, id, approve, reason, &admin)
+    }
 
-        match kind.as_bytes() {
-            b"cedente" => {
-                let mut e: EntityRecord = env.storage().persistent().get(&DataKey::Cedente(id.clone())).ok_or(VaultError::NotFound)?;
-                e.status = st;
-                env.storage().persistent().set(&DataKey::Cedente(id.clone()), &e);
-            }
-            b"sacado" => {
-                let mut e: EntityRecord = env.storage().persistent().get(&DataKey::Sacado(id.clone())).ok_or(VaultError::NotFound)?;
-                e.status = st;
-                env.storage().persistent().set(&DataKey::Sacado(id.clone()), &e);
+    fn review_entity(env: &Env, kind: EntityKind, id: String, approve: bool, reason: Option<String>, by: &Address) -> Result<(), VaultError> {
+        if let Status::Pending = Self::entity_status(env, &kind, &id)? {} else { return Err(VaultError::BadStatus); }
+        let st = if approve {
+            Status::Approved
+        } else {
+            match &reason { Some(r) if r.len() > 0 => {}, _ => return Err(VaultError::Invalid) }
+            Status::Rejected
+        };
+        Self::set_entity_status(env, &kind, &id, st, by, reason)?;
+        env.events().publish((symbol_short!("ent_appr"), kind, id), approve);
+        Ok(())
+    }
+
+    /// Fundo ao qual o cadastro pertence (o próprio id, para fundos).
+    fn entity_fund(env: &Env, kind: &EntityKind, id: &String) -> Result<String, VaultError> {
+        Ok(match kind {
+            EntityKind::Cedente => env.storage().persistent().get::<_, EntityRecord>(&DataKey::Cedente(id.clone())).ok_or(VaultError::NotFound)?.fund_id,
+            EntityKind::Sacado => env.storage().persistent().get::<_, EntityRecord>(&DataKey::Sacado(id.clone())).ok_or(VaultError::NotFound)?.fund_id,
+            EntityKind::Fund => id.clone(),
+        })
+    }
+
+    /// Consultor autor reenvia um cadastro rejeitado (opcionalmente com novo nome) para revisão.
+    /// Precisa continuar designado para o fundo do cadastro.
+    pub fn resubmit_entity(env: Env, consultant: Address, kind: EntityKind, id: String, name: Option<String>) -> Result<(), VaultError> {
+        consultant.require_auth();
+        if !Self::is_consultant(&env, &consultant) { return Err(VaultError::NotConsultant); }
+        if let Some(n) = &name { if n.len() == 0 { return Err(VaultError::Invalid); } }
+        if let Status::Rejected = Self::entity_status(&env, &kind, &id)? {} else { return Err(VaultError::BadStatus); }
+
+        match kind {
+            EntityKind::Cedente | EntityKind::Sacado => {
+                let key = if let EntityKind::Cedente = kind { DataKey::Cedente(id.clone()) } else { DataKey::Sacado(id.clone()) };
+                let mut e: EntityRecord = env.storage().persistent().get(&key).unwrap();
+                if e.created_by != consultant { return Err(VaultError::Unauthorized); }
+                if !Self::is_assigned(env.clone(), consultant.clone(), e.fund_id.clone()) { return Err(VaultError::NotAssigned); }
+                if let Some(n) = name { e.name = n; }
+                env.storage().persistent().set(&key, &e);
             }
-            b"fund" => {
-                let mut f: FundRecord = env.storage().persistent().get(&DataKey::Fund(id.clone())).ok_or(VaultError::NotFound)?;
-                f.status = st;
+            EntityKind::Fund => {
+                let mut f: FundRecord = env.storage().persistent().get(&DataKey::Fund(id.clone())).unwrap();
+                if f.created_by != consultant { return Err(VaultError::Unauthorized); }
+                if !Self::is_assigned(env.clone(), consultant.clone(), id.clone()) { return Err(VaultError::NotAssigned); }
+                if let Some(n) = name { f.name = n; }
                 env.storage().persistent().set(&DataKey::Fund(id.clone()), &f);
             }
-            _ => return Err(VaultError::Invalid),
         }
-        env.events().publish((symbol_short!("entity_approve"), kind, id), approve);
+        Self::set_entity_status(&env, &kind, &id, Status::Pending, &consultant, None)?;
+        env.events().publish((symbol_short!("ent_resub"), kind, id), consultant);
         Ok(())
     }
 
-    // -------- Recebíveis --------
-    pub fn register_receivable(
-        env: Env,
-        id: String,
-        fund_id: String,
-        cedente_id: String,
-        sacado_id: String,
-        face_value: i128,
-        due_date: u64,
-        debtor_address: Address,
-    ) -> Result<(), VaultError> {
+    /// Suspende um cadastro aprovado: deixa de aceitar novos recebíveis até ser reativado.
+    pub fn suspend_entity(env: Env, kind: EntityKind, id: String, reason: String) -> Result<(), VaultError> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
+        if reason.len() == 0 { return Err(VaultError::Invalid); }
+        if let Status::Approved = Self::entity_status(&env, &kind, &id)? {} else { return Err(VaultError::BadStatus); }
+        Self::set_entity_status(&env, &kind, &id, Status::Suspended, &admin, Some(reason))?;
+        env.events().publish((symbol_short!("ent_susp"), kind, id), ());
+        Ok(())
+    }
 
-        if face_value <= 0 { return Err(VaultError::Invalid); }
-        if env.storage().persistent().has(&DataKey::Receivable(id.clone())) { return Err(VaultError::DuplicateId); }
+    pub fn reactivate_entity(env: Env, kind: EntityKind, id: String) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if let Status::Suspended = Self::entity_status(&env, &kind, &id)? {} else { return Err(VaultError::BadStatus); }
+        Self::set_entity_status(&env, &kind, &id, Status::Approved, &admin, None)?;
+        env.events().publish((symbol_short!("ent_react"), kind, id), ());
+        Ok(())
+    }
 
-        // checagens de aprovação
-        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
-        if let Status::Approved = fund.status {} else { return Err(VaultError::BadStatus); }
+    pub fn status_history(env: Env, kind: EntityKind, id: String) -> Vec<StatusChange> {
+        env.storage().persistent().get(&DataKey::StatusHistory(kind, id)).unwrap_or(Vec::new(&env))
+    }
 
-        let sac: EntityRecord = env.storage().persistent().get(&DataKey::Sacado(sacado_id.clone())).ok_or(VaultError::NotFound)?;
-        if let Status::Approved = sac.status {} else { return Err(VaultError::BadStatus); }
+    fn entity_status(env: &Env, kind: &EntityKind, id: &String) -> Result<Status, VaultError> {
+        Ok(match kind {
+            EntityKind::Cedente => env.storage().persistent().get::<_, EntityRecord>(&DataKey::Cedente(id.clone())).ok_or(VaultError::NotFound)?.status,
+            EntityKind::Sacado => env.storage().persistent().get::<_, EntityRecord>(&DataKey::Sacado(id.clone())).ok_or(VaultError::NotFound)?.status,
+            EntityKind::Fund => env.storage().persistent().get::<_, FundRecord>(&DataKey::Fund(id.clone())).ok_or(VaultError::NotFound)?.status,
+        })
+    }
+
+    /// Grava o novo status no cadastro e acrescenta a transição ao histórico.
+    fn set_entity_status(env: &Env, kind: &EntityKind, id: &String, st: Status, by: &Address, reason: Option<String>) -> Result<(), VaultError> {
+        let now = env.ledger().timestamp();
+        // aprovação registra quem/quando; rejeição guarda o motivo; reenvio limpa ambos.
+        // Suspensão preserva a aprovação original (o motivo fica no histórico).
+        let approval = if let Status::Approved = st { Some(by.clone()) } else { None };
+        let rejection = if let Status::Rejected = st { reason.clone() } else { None };
+        let keeps_approval = matches!(st, Status::Suspended);
+        match kind {
+            EntityKind::Cedente | EntityKind::Sacado => {
+                let key = if let EntityKind::Cedente = kind { DataKey::Cedente(id.clone()) } else { DataKey::Sacado(id.clone()) };
+                let mut e: EntityRecord = env.storage().persistent().get(&key).ok_or(VaultError::NotFound)?;
+                if !keeps_approval {
+                    e.approved_at = if approval.is_some() { now } else { 0 };
+                    e.approved_by = approval;
+                    e.rejection_reason = rejection;
+                }
+                e.status = st.clone();
+                env.storage().persistent().set(&key, &e);
+            }
+            EntityKind::Fund => {
+                let mut f: FundRecord = env.storage().persistent().get(&DataKey::Fund(id.clone())).ok_or(VaultError::NotFound)?;
+                if !keeps_approval {
+                    f.approved_at = if approval.is_some() { now } else { 0 };
+                    f.approved_by = approval;
+                    f.rejection_reason = rejection;
+                }
+                f.status = st.clone();
+                env.storage().persistent().set(&DataKey::Fund(id.clone()), &f);
+            }
+        }
+        Self::push_history(env, kind.clone(), id, st, by, reason);
+        Ok(())
+    }
+
+    fn push_history(env: &Env, kind: EntityKind, id: &String, status: Status, by: &Address, reason: Option<String>) {
+        let key = DataKey::StatusHistory(kind, id.clone());
+        let mut history: Vec<StatusChange> = env.storage().persistent().get(&key).unwrap_or(Vec::new(env));
+        history.push_back(StatusChange { status, by: by.clone(), at: env.ledger().timestamp(), reason });
+        env.storage().persistent().set(&key, &history);
+    }
+
+    // -------- Aprovação M-de-N --------
+    /// Configura os aprovadores do fundo. O gestor só define o primeiro conjunto; trocar ou
+    /// remover (`approvers` vazio volta à aprovação só pelo gestor) exige `SetApprovers` com quórum.
+    pub fn set_approvers(env: Env, fund_id: String, approvers: Vec<Address>, threshold: u32, ttl: u64, receivable_limit: i128) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if Self::needs_proposal(&env, &fund_id, None) { return Err(VaultError::NeedsProposal); }
+        Self::write_approvers(&env, fund_id, approvers, threshold, ttl, receivable_limit)
+    }
+
+    fn write_approvers(env: &Env, fund_id: String, approvers: Vec<Address>, threshold: u32, ttl: u64, receivable_limit: i128) -> Result<(), VaultError> {
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        let key = DataKey::Approvers(fund_id.clone());
+        if approvers.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            let mut seen: Map<Address, bool> = Map::new(env);
+            for a in approvers.iter() {
+                if seen.contains_key(a.clone()) { return Err(VaultError::DuplicateId); }
+                seen.set(a, true);
+            }
+            if threshold == 0 || threshold > approvers.len() || ttl == 0 || receivable_limit < 0 { return Err(VaultError::Invalid); }
+            env.storage().persistent().set(&key, &ApproverSet { approvers: approvers.clone(), threshold, ttl, receivable_limit });
+        }
+        env.events().publish((symbol_short!("appr_set"), fund_id), (approvers.len(), threshold));
+        Ok(())
+    }
+
+    pub fn approvers(env: Env, fund_id: String) -> Option<ApproverSet> {
+        env.storage().persistent().get(&DataKey::Approvers(fund_id))
+    }
+
+    /// `face_value` só se aplica a registros: abaixo do limite o gestor registra sozinho.
+    fn needs_proposal(env: &Env, fund_id: &String, face_value: Option<i128>) -> bool {
+        match Self::approvers(env.clone(), fund_id.clone()) {
+            None => false,
+            Some(set) => face_value.map_or(true, |v| v > set.receivable_limit),
+        }
+    }
+
+    /// Abre uma proposta; a assinatura do proponente conta como a primeira aprovação.
+    pub fn propose(env: Env, approver: Address, action: ProposalAction) -> Result<u32, VaultError> {
+        approver.require_auth();
+        let fund_id = match &action {
+            ProposalAction::ApproveEntity(kind, id, _, _) => Self::entity_fund(&env, kind, id)?,
+            ProposalAction::RegisterReceivable(it) => it.fund_id.clone(),
+            ProposalAction::Distribute(rid, _)
+            | ProposalAction::DistributeClaimable(rid, _)
+            | ProposalAction::StartDistribution(rid, _)
+            | ProposalAction::DistributeInstallment(rid, _, _) => Self::receivable_fund(&env, rid)?,
+            ProposalAction::PublishMerkleRoot(m) => m.fund_id.clone(),
+            ProposalAction::SetApprovers(fund_id, _, _, _, _) => fund_id.clone(),
+        };
+        let set = Self::approvers(env.clone(), fund_id.clone()).ok_or(VaultError::NotFound)?;
+        if !set.approvers.contains(&approver) { return Err(VaultError::NotApprover); }
+
+        let id: u32 = env.storage().instance().get(&DataKey::ProposalCount).unwrap_or(0);
+        env.storage().instance().set(&DataKey::ProposalCount, &(id + 1));
+        let now = env.ledger().timestamp();
+        let mut p = Proposal {
+            id,
+            fund_id: fund_id.clone(),
+            action,
+            approvals: vec![&env, approver.clone()],
+            created_at: now,
+            expires_at: now.saturating_add(set.ttl),
+            executed: false,
+        };
+        env.events().publish((symbol_short!("prop_new"), fund_id, id), approver.clone());
+        if set.threshold <= 1 { Self::execute_proposal(&env, &mut p, &approver)?; }
+        env.storage().persistent().set(&DataKey::Proposal(id), &p);
+        Ok(id)
+    }
+
+    /// Registra a aprovação; ao atingir o quórum a ação é executada na mesma chamada.
+    pub fn approve_proposal(env: Env, approver: Address, id: u32) -> Result<bool, VaultError> {
+        approver.require_auth();
+        let mut p: Proposal = env.storage().persistent().get(&DataKey::Proposal(id)).ok_or(VaultError::NotFound)?;
+        if p.executed { return Err(VaultError::BadStatus); }
+        if env.ledger().timestamp() > p.expires_at { return Err(VaultError::Expired); }
+        // o conjunto vigente vale: aprovadores removidos depois da proposta deixam de contar
+        let set = Self::approvers(env.clone(), p.fund_id.clone()).ok_or(VaultError::NotFound)?;
+        if !set.approvers.contains(&approver) { return Err(VaultError::NotApprover); }
+        if p.approvals.contains(&approver) { return Err(VaultError::AlreadyApproved); }
+
+        p.approvals.push_back(approver.clone());
+        env.events().publish((symbol_short!("prop_appr"), p.fund_id.clone(), id), approver.clone());
+        let valid = p.approvals.iter().filter(|a| set.approvers.contains(a)).count() as u32;
+        if valid >= set.threshold { Self::execute_proposal(&env, &mut p, &approver)?; }
+        env.storage().persistent().set(&DataKey::Proposal(id), &p);
+        Ok(p.executed)
+    }
+
+    fn execute_proposal(env: &Env, p: &mut Proposal, by: &Address) -> Result<(), VaultError> {
+        match p.action.clone() {
+            ProposalAction::ApproveEntity(kind, id, approve, reason) => Self::review_entity(env, kind, id, approve, reason, by)?,
+            ProposalAction::RegisterReceivable(it) => {
+                let consultant = Self::check_new_receivable(env, &it.id, &it.fund_id, &it.cedente_id, &it.sacado_id, it.face_value)?;
+                Self::insert_receivable(env, it, consultant);
+            }
+            ProposalAction::Distribute(rid, holders) => Self::distribute_receivable(env, rid, holders)?,
+            ProposalAction::DistributeClaimable(rid, from) => Self::launch_claimable(env.clone(), from, rid)?,
+            ProposalAction::StartDistribution(rid, holders) => Self::start_run(env.clone(), rid, holders)?,
+            ProposalAction::DistributeInstallment(rid, index, holders) => Self::distribute_paid_installment(env.clone(), rid, index, holders)?,
+            ProposalAction::PublishMerkleRoot(m) => Self::publish_root(env.clone(), m)?,
+            ProposalAction::SetApprovers(fund_id, approvers, threshold, ttl, limit) => Self::write_approvers(env, fund_id, approvers, threshold, ttl, limit)?,
+        }
+        p.executed = true;
+        env.events().publish((symbol_short!("prop_exec"), p.fund_id.clone(), p.id), p.approvals.len());
+        Ok(())
+    }
+
+    pub fn get_proposal(env: Env, id: u32) -> Result<Proposal, VaultError> {
+        env.storage().persistent().get(&DataKey::Proposal(id)).ok_or(VaultError::NotFound)
+    }
+
+    fn receivable_fund(env: &Env, id: &String) -> Result<String, VaultError> {
+        let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id.clone())).ok_or(VaultError::NotFound)?;
+        Ok(r.fund_id)
+    }
+
+    // -------- Recebíveis --------
+    pub fn register_receivable(
+        env: Env,
+        id: String,
+        fund_id: String,
+        cedente_id: String,
+        sacado_id: String,
+        face_value: i128,
+        due_date: u64,
+        debtor_address: Address,
+    ) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if Self::needs_proposal(&env, &fund_id, Some(face_value)) { return Err(VaultError::NeedsProposal); }
+
+        let it = ReceivableInput { id, fund_id, cedente_id, sacado_id, face_value, due_date, debtor_address };
+        let consultant = Self::check_new_receivable(&env, &it.id, &it.fund_id, &it.cedente_id, &it.sacado_id, it.face_value)?;
+        Self::insert_receivable(&env, it, consultant);
+        Ok(())
+    }
+
+    fn insert_receivable(env: &Env, it: ReceivableInput, consultant: Address) {
+        let r = Receivable {
+            id: it.id,
+            fund_id: it.fund_id,
+            cedente_id: it.cedente_id,
+            sacado_id: it.sacado_id,
+            kind: ReceivableKind::Single,
+            face_value: it.face_value,
+            due_date: it.due_date,
+            debtor_address: it.debtor_address,
+            status: ReceivableStatus::Pending,
+            paid_amount: 0,
+            paid_date: 0,
+            consultant,
+            documents: Vec::new(env),
+        };
+        Self::store_receivable(env, &r);
+    }
+
+    /// Recebível parcelado: `schedule` = [(vencimento, valor)] em ordem crescente de vencimento.
+    pub fn register_installment_receivable(
+        env: Env,
+        id: String,
+        fund_id: String,
+        cedente_id: String,
+        sacado_id: String,
+        schedule: Vec<(u64, i128)>,
+        debtor_address: Address,
+    ) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if schedule.len() == 0 || schedule.len() > MAX_INSTALLMENTS { return Err(VaultError::Invalid); }
+        let mut total: i128 = 0;
+        for (_, amount) in schedule.iter() { total = total.saturating_add(amount); }
+        if Self::needs_proposal(&env, &fund_id, Some(total)) { return Err(VaultError::NeedsProposal); }
+        if env.storage().persistent().has(&DataKey::Receivable(id.clone())) { return Err(VaultError::DuplicateId); }
+        let consultant = Self::check_parties(&env, &fund_id, &cedente_id, &sacado_id)?;
 
-        let _ced: EntityRecord = env.storage().persistent().get(&DataKey::Cedente(cedente_id.clone())).ok_or(VaultError::NotFound)?; // status opcional
+        let mut installments: Vec<Installment> = vec![&env];
+        let mut face_value: i128 = 0;
+        let mut last_due: u64 = 0;
+        for (due_date, amount) in schedule.iter() {
+            if amount <= 0 { return Err(VaultError::Invalid); }
+            if installments.len() > 0 && due_date <= last_due { return Err(VaultError::Invalid); }
+            face_value = add(face_value, amount)?;
+            last_due = due_date;
+            installments.push_back(Installment { due_date, amount, status: ReceivableStatus::Pending, paid_amount: 0, paid_date: 0 });
+        }
 
         let r = Receivable {
             id: id.clone(),
-            fund_id: fund_id.clone(),
+            fund_id,
             cedente_id,
             sacado_id,
+            kind: ReceivableKind::Installments,
             face_value,
-            due_date,
+            due_date: last_due,
             debtor_address,
             status: ReceivableStatus::Pending,
             paid_amount: 0,
             paid_date: 0,
+            consultant,
+            documents: Vec::new(&env),
         };
-        env.storage().persistent().set(&DataKey::Receivable(id.clone()), &r);
+        env.storage().persistent().set(&DataKey::Installments(id), &installments);
+        Self::store_receivable(&env, &r);
+        Ok(())
+    }
+
+    /// Registra vários recebíveis de uma vez (tudo ou nada). Nenhum item é gravado se algum
+    /// falhar na validação; nesse caso retorna `Failed(índice, erro)`.
+    pub fn register_receivables(env: Env, inputs: Vec<ReceivableInput>) -> Result<BatchResult, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if inputs.len() == 0 { return Err(VaultError::Invalid); }
+        if inputs.len() > MAX_BATCH { return Err(VaultError::BatchTooLarge); }
+
+        // 1ª passada: só valida (inclusive ids repetidos dentro do lote)
+        let mut seen: Map<String, bool> = Map::new(&env);
+        let mut consultants: Vec<Address> = vec![&env];
+        for (i, it) in inputs.iter().enumerate() {
+            if seen.contains_key(it.id.clone()) { return Ok(BatchResult::Failed(i as u32, VaultError::DuplicateId as u32)); }
+            seen.set(it.id.clone(), true);
+            if Self::needs_proposal(&env, &it.fund_id, Some(it.face_value)) {
+                return Ok(BatchResult::Failed(i as u32, VaultError::NeedsProposal as u32));
+            }
+            match Self::check_new_receivable(&env, &it.id, &it.fund_id, &it.cedente_id, &it.sacado_id, it.face_value) {
+                Ok(c) => consultants.push_back(c),
+                Err(e) => return Ok(BatchResult::Failed(i as u32, e as u32)),
+            }
+        }
+        // 2ª passada: grava
+        for (it, consultant) in inputs.iter().zip(consultants.iter()) {
+            Self::insert_receivable(&env, it, consultant);
+        }
+        Ok(BatchResult::Done(inputs.len()))
+    }
+
+    /// Valida um novo recebível e retorna o consultor que originou o cedente.
+    fn check_new_receivable(env: &Env, id: &String, fund_id: &String, cedente_id: &String, sacado_id: &String, face_value: i128) -> Result<Address, VaultError> {
+        if face_value <= 0 { return Err(VaultError::Invalid); }
+        if env.storage().persistent().has(&DataKey::Receivable(id.clone())) { return Err(VaultError::DuplicateId); }
+        Self::check_parties(env, fund_id, cedente_id, sacado_id)
+    }
+
+    fn check_parties(env: &Env, fund_id: &String, cedente_id: &String, sacado_id: &String) -> Result<Address, VaultError> {
+        // checagens de aprovação e de vínculo com o fundo
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        match fund.status {
+            Status::Approved => {}
+            Status::Suspended => return Err(VaultError::FundSuspended),
+            _ => return Err(VaultError::FundNotApproved),
+        }
+
+        let ced: EntityRecord = env.storage().persistent().get(&DataKey::Cedente(cedente_id.clone())).ok_or(VaultError::NotFound)?;
+        match ced.status {
+            Status::Approved => {}
+            Status::Suspended => return Err(VaultError::CedenteSuspended),
+            _ => return Err(VaultError::CedenteNotApproved),
+        }
+        if ced.fund_id != *fund_id { return Err(VaultError::CedenteNotInFund); }
+
+        let sac: EntityRecord = env.storage().persistent().get(&DataKey::Sacado(sacado_id.clone())).ok_or(VaultError::NotFound)?;
+        match sac.status {
+            Status::Approved => {}
+            Status::Suspended => return Err(VaultError::SacadoSuspended),
+            _ => return Err(VaultError::SacadoNotApproved),
+        }
+        if sac.fund_id != *fund_id { return Err(VaultError::SacadoNotInFund); }
+        Ok(ced.created_by)
+    }
+
+    fn store_receivable(env: &Env, r: &Receivable) {
+        env.storage().persistent().set(&DataKey::Receivable(r.id.clone()), r);
 
         let cnt: u32 = env.storage().instance().get(&DataKey::ReceivableCount).unwrap_or(0);
         env.storage().instance().set(&DataKey::ReceivableCount, &(cnt + 1));
-        env.events().publish((symbol_short!("rcv_reg"), fund_id, id), face_value);
-        Ok(())
+
+        let pos: u32 = env.storage().persistent().get(&DataKey::FundReceivableCount(r.fund_id.clone())).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::FundReceivable(r.fund_id.clone(), pos), &r.id);
+        env.storage().persistent().set(&DataKey::FundReceivableCount(r.fund_id.clone()), &(pos + 1));
+
+        let key = DataKey::Production(r.consultant.clone(), r.fund_id.clone());
+        let mut p: Production = env.storage().persistent().get(&key).unwrap_or_default();
+        p.count += 1;
+        p.face_value = p.face_value.saturating_add(r.face_value);
+        env.storage().persistent().set(&key, &p);
+        env.events().publish((symbol_short!("rcv_reg"), r.fund_id.clone(), r.id.clone()), r.face_value);
     }
 
     pub fn mark_paid(env: Env, id: String, amount: i128) -> Result<(), VaultError> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
-        if amount <= 0 { return Err(VaultError::Invalid); }
 
-        let mut r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id.clone())).ok_or(VaultError::NotFound)?;
+        let r = Self::check_payment(&env, &id, amount)?;
+        Self::apply_payment(&env, r, amount)
+    }
+
+    /// Baixa vários recebíveis de uma vez (tudo ou nada), mesma semântica de `register_receivables`.
+    pub fn mark_paid_batch(env: Env, payments: Vec<(String, i128)>) -> Result<BatchResult, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if payments.len() == 0 { return Err(VaultError::Invalid); }
+        if payments.len() > MAX_BATCH { return Err(VaultError::BatchTooLarge); }
+
+        let mut checked: Vec<Receivable> = vec![&env];
+        let mut seen: Map<String, bool> = Map::new(&env);
+        for (i, (id, amount)) in payments.iter().enumerate() {
+            if seen.contains_key(id.clone()) { return Ok(BatchResult::Failed(i as u32, VaultError::DuplicateId as u32)); }
+            seen.set(id.clone(), true);
+            match Self::check_payment(&env, &id, amount) {
+                Ok(r) => checked.push_back(r),
+                Err(e) => return Ok(BatchResult::Failed(i as u32, e as u32)),
+            }
+        }
+        for (r, (_, amount)) in checked.iter().zip(payments.iter()) {
+            Self::apply_payment(&env, r, amount)?;
+        }
+        Ok(BatchResult::Done(payments.len()))
+    }
+
+    fn check_payment(env: &Env, id: &String, amount: i128) -> Result<Receivable, VaultError> {
+        if amount <= 0 { return Err(VaultError::Invalid); }
+        let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id.clone())).ok_or(VaultError::NotFound)?;
+        if let ReceivableKind::Installments = r.kind { return Err(VaultError::BadStatus); } // use mark_installment_paid
         match r.status {
             ReceivableStatus::Pending => {},
             _ => return Err(VaultError::BadStatus),
         }
+        Ok(r)
+    }
 
-        r.status = ReceivableStatus::Paid;
-        r.paid_amount = amount;
+    /// Pagamento a menor fica registrado e o título segue em aberto pelo saldo (face - pago);
+    /// só quitado ele passa a `Paid`.
+    fn apply_payment(env: &Env, mut r: Receivable, amount: i128) -> Result<(), VaultError> {
+        r.paid_amount = add(r.paid_amount, amount)?;
         r.paid_date = env.ledger().timestamp();
-        env.storage().persistent().set(&DataKey::Receivable(id.clone()), &r);
+        if r.paid_amount >= r.face_value { r.status = ReceivableStatus::Paid; }
+        env.storage().persistent().set(&DataKey::Receivable(r.id.clone()), &r);
 
         let tot: i128 = env.storage().instance().get(&DataKey::TotalPaid).unwrap_or(0);
         env.storage().instance().set(&DataKey::TotalPaid, &add(tot, amount)?);
-        env.events().publish((symbol_short!("rcv_paid"), r.fund_id, id), amount);
+        Self::accrue_commission(env, &r, amount)?;
+        env.events().publish((symbol_short!("rcv_paid"), r.fund_id, r.id), amount);
         Ok(())
     }
 
@@ -248,71 +1361,2128 @@ impl ReceivableVault {
     pub fn distribute(env: Env, receivable_id: String, holders: Vec<Address>) -> Result<(), VaultError> {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
+        let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if Self::needs_proposal(&env, &r.fund_id, None) { return Err(VaultError::NeedsProposal); }
+        Self::distribute_receivable(&env, receivable_id, holders)
+    }
 
-        let mut r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id.clone())).ok_or(VaultError::NotFound)?;
-        if let ReceivableStatus::Paid = r.status {} else { return Err(VaultError::BadStatus); }
+    fn distribute_receivable(env: &Env, receivable_id: String, holders: Vec<Address>) -> Result<(), VaultError> {
+        let mut r = Self::distributable(env, &receivable_id)?;
+        let paid = r.paid_amount;
+        Self::distribute_amount(env, &r, &holders, paid)?;
 
-        // pega o token do fundo
-        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(r.fund_id.clone())).ok_or(VaultError::NotFound)?;
-        let token_addr = fund.token;
+        r.status = ReceivableStatus::Distributed;
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id.clone()), &r);
+        env.events().publish((symbol_short!("dist_sum"), r.fund_id, receivable_id), paid);
+        Ok(())
+    }
 
-        // soma saldos
-        let mut total_shares: i128 = 0;
-        let mut balances: Vec<i128> = vec![&env];
-        let mut max_idx: i32 = -1;
-        let mut max_bal: i128 = 0;
+    /// Simula `distribute` sem alterar estado: mesmas validações e mesmo rateio.
+    pub fn preview_distribution(env: Env, receivable_id: String, holders: Vec<Address>) -> Result<DistributionPreview, VaultError> {
+        let r = Self::distributable(&env, &receivable_id)?;
+        Self::ensure_single_class(&env, &r.fund_id)?;
+        let (buckets, takes, holdback, net) = Self::waterfall_split(&env, &r, r.paid_amount)?;
+        let (_, shares, extra) = Self::compute_shares(&env, &r.fund_id, &holders, net)?;
 
+        let mut preview = DistributionPreview { waterfall: vec![&env], shares: vec![&env], residue: vec![&env] };
+        for (b, take) in buckets.iter().zip(takes.iter()) {
+            let to = if let BucketKind::Reserve | BucketKind::Commission = b.kind { env.current_contract_address() } else { b.recipient };
+            preview.waterfall.push_back((to, take));
+        }
+        if holdback > 0 { preview.waterfall.push_back((env.current_contract_address(), holdback)); }
         for (i, h) in holders.iter().enumerate() {
-            // cross-contract call: FundToken::balance(env, h)
-            let bal: i128 = token_addr
-                .call::<i128>(&env, symbol_short!("balance"), (h.clone(),));
-            balances.push_back(bal);
-            total_shares = add(total_shares, bal)?;
-            if bal > max_bal { max_bal = bal; max_idx = i as i32; }
+            preview.shares.push_back((h.clone(), shares.get(i as u32).unwrap()));
+            let e = extra.get(i as u32).unwrap();
+            if e > 0 { preview.residue.push_back((h, e)); }
         }
-        if total_shares <= 0 { return Err(VaultError::Invalid); }
+        Ok(preview)
+    }
 
-        // distribuição
-        let paid = r.paid_amount;
-        let mut distributed_sum: i128 = 0;
+    fn distributable(env: &Env, receivable_id: &String) -> Result<Receivable, VaultError> {
+        let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if let ReceivableKind::Installments = r.kind { return Err(VaultError::BadStatus); } // use distribute_installment
+        if let ReceivableStatus::Paid = r.status {} else { return Err(VaultError::BadStatus); }
+        Ok(r)
+    }
+
+    /// Paga a cascata do fundo e rateia o restante de `paid` entre os `holders` pró-rata pelos
+    /// saldos no FundToken do fundo.
+    fn distribute_amount(env: &Env, r: &Receivable, holders: &Vec<Address>, paid: i128) -> Result<(), VaultError> {
+        let fund_id = &r.fund_id;
+        Self::ensure_single_class(env, fund_id)?;
+        let net = Self::pay_waterfall(env, r, paid)?;
+        let (token, shares, _) = Self::compute_shares(env, fund_id, holders, net)?;
+        let policy = Self::holder_policy(env.clone(), fund_id.clone());
 
         for (i, h) in holders.iter().enumerate() {
-            let bal = balances.get(i as u32).unwrap();
-            if *bal == 0 { continue; }
-            let share = (*bal * paid) / total_shares;
+            let share = shares.get(i as u32).unwrap();
             if share > 0 {
-                distributed_sum = add(distributed_sum, share)?;
                 // emit apenas evento (MVP). Em produção, faça transferências do ativo de pagamento.
-                env.events().publish((symbol_short!("dist"), r.fund_id.clone(), h.clone()), share);
+                Self::pay_holder(env, fund_id, &token, &policy, h, share, symbol_short!("dist"))?;
             }
         }
-        // resíduo
-        let residue = sub(paid, distributed_sum)?;
-        if residue > 0 && max_idx >= 0 {
-            let h = holders.get(max_idx as u32).unwrap();
-            env.events().publish((symbol_short!("dist_residue"), r.fund_id.clone(), h.clone()), residue);
+        Ok(())
+    }
+
+    // -------- Cascata de pagamentos --------
+    pub fn set_waterfall(env: Env, fund_id: String, buckets: Vec<WaterfallBucket>) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        if buckets.len() > MAX_BUCKETS { return Err(VaultError::Invalid); }
+
+        let mut total_bps: i128 = 0;
+        let mut commission = false;
+        for b in buckets.iter() {
+            if let BucketKind::Commission = b.kind {
+                if commission { return Err(VaultError::Invalid); }
+                commission = true;
+            }
+            match b.amount {
+                BucketAmount::Bps(bps) => total_bps += bps as i128,
+                BucketAmount::Fixed(v) => if v <= 0 { return Err(VaultError::Invalid); },
+            }
         }
+        if total_bps > BPS { return Err(VaultError::Invalid); }
 
-        r.status = ReceivableStatus::Distributed;
-        env.storage().persistent().set(&DataKey::Receivable(receivable_id.clone()), &r);
-        env.events().publish((symbol_short!("dist_sum"), r.fund_id, receivable_id), paid);
+        env.storage().persistent().set(&DataKey::Waterfall(fund_id.clone()), &buckets);
+        env.events().publish((symbol_short!("wf_set"), fund_id), buckets.len());
         Ok(())
     }
 
-    // -------- Views --------
-    pub fn get_receivable(env: Env, id: String) -> Result<Receivable, VaultError> {
-        env.storage().persistent().get(&DataKey::Receivable(id)).ok_or(VaultError::NotFound)
+    pub fn waterfall(env: Env, fund_id: String) -> Vec<WaterfallBucket> {
+        env.storage().persistent().get(&DataKey::Waterfall(fund_id)).unwrap_or(vec![&env])
     }
-    pub fn get_receivables_count(env: Env) -> u32 {
-        env.storage().instance().get(&DataKey::ReceivableCount).unwrap_or(0)
+
+    /// Calcula quanto cada faixa leva de `paid` (pagamento do título `r`), em ordem, quanto fica
+    /// retido para recompor a reserva até a meta e o que sobra para os cotistas.
+    fn waterfall_split(env: &Env, r: &Receivable, paid: i128) -> Result<(Vec<WaterfallBucket>, Vec<i128>, i128, i128), VaultError> {
+        let fund_id = &r.fund_id;
+        let buckets = Self::waterfall(env.clone(), fund_id.clone());
+        let mut takes: Vec<i128> = vec![env];
+        let mut rest = paid;
+        let mut reserve = Self::reserve_balance(env.clone(), fund_id.clone());
+        for b in buckets.iter() {
+            let mut due = match b.amount {
+                BucketAmount::Bps(bps) => paid.checked_mul(bps as i128).ok_or(VaultError::Math)? / BPS,
+                BucketAmount::Fixed(v) => v,
+            };
+            if let BucketKind::Commission = b.kind {
+                // só o que falta provisionar para o consultor deste título: um pagamento não
+                // provisiona comissão de outro consultor
+                let unfunded = sub(
+                    Self::commission_due(env.clone(), r.consultant.clone(), fund_id.clone()),
+                    Self::commission_funded(env.clone(), r.consultant.clone(), fund_id.clone()),
+                )?;
+                due = core::cmp::min(due, unfunded);
+            }
+            let take = core::cmp::min(due, rest);
+            rest = sub(rest, take)?;
+            if let BucketKind::Reserve = b.kind { reserve = add(reserve, take)?; }
+            takes.push_back(take);
+        }
+        // reserva abaixo da meta: retém do que iria para os cotistas
+        let shortfall = sub(Self::reserve_target(env.clone(), fund_id.clone())?, reserve)?;
+        let holdback = if shortfall > 0 { core::cmp::min(shortfall, rest) } else { 0 };
+        rest = sub(rest, holdback)?;
+        Ok((buckets, takes, holdback, rest))
     }
-    pub fn get_total_paid(env: Env) -> i128 {
-        env.storage().instance().get(&DataKey::TotalPaid).unwrap_or(0)
+
+    /// Paga as faixas da cascata (evento por faixa), retém o que faltar para a meta da reserva e
+    /// retorna o valor líquido para os cotistas.
+    fn pay_waterfall(env: &Env, r: &Receivable, paid: i128) -> Result<i128, VaultError> {
+        let (buckets, takes, holdback, net) = Self::waterfall_split(env, r, paid)?;
+        Self::apply_waterfall(env, r, &buckets, &takes, holdback)?;
+        Ok(net)
     }
-    pub fn get_fund_token_address(env: Env) -> Address {
-        env.storage().instance().get(&DataKey::FundTokenAddress).unwrap()
+
+    fn apply_waterfall(env: &Env, r: &Receivable, buckets: &Vec<WaterfallBucket>, takes: &Vec<i128>, holdback: i128) -> Result<(), VaultError> {
+        let fund_id = &r.fund_id;
+        for (i, (b, take)) in buckets.iter().zip(takes.iter()).enumerate() {
+            if take == 0 { continue; }
+            match b.kind {
+                BucketKind::Reserve => {
+                    Self::credit_reserve(env, fund_id, take)?;
+                    env.events().publish((symbol_short!("wf_pay"), fund_id.clone(), i as u32, b.kind), take);
+                }
+                BucketKind::Commission => {
+                    Self::bump(env, DataKey::CommissionUnfunded(fund_id.clone()), -take)?;
+                    Self::bump(env, DataKey::CommissionPool(fund_id.clone()), take)?;
+                    Self::bump(env, DataKey::CommissionFunded(r.consultant.clone(), fund_id.clone()), take)?;
+                    env.events().publish((symbol_short!("wf_pay"), fund_id.clone(), i as u32, b.kind), take);
+                }
+                _ => {
+                    // emit apenas evento (MVP), como os pagamentos aos cotistas
+                    env.events().publish((symbol_short!("wf_pay"), fund_id.clone(), i as u32, b.kind), (b.recipient, take));
+                }
+            }
+        }
+        if holdback > 0 {
+            Self::credit_reserve(env, fund_id, holdback)?;
+            env.events().publish((symbol_short!("rsv_hold"), fund_id.clone()), holdback);
+        }
+        Ok(())
     }
-    pub fn get_admin(env: Env) -> Address {
-        env.storage().instance().get(&DataKey::Admin).unwrap()
+
+    /// Desfaz `apply_waterfall` (distribuição paginada cancelada). Falha se a reserva ou a
+    /// comissão creditadas já foram sacadas.
+    fn reverse_waterfall(env: &Env, r: &Receivable, buckets: &Vec<WaterfallBucket>, takes: &Vec<i128>, holdback: i128) -> Result<(), VaultError> {
+        let fund_id = &r.fund_id;
+        let mut reserve: i128 = holdback;
+        for (i, (b, take)) in buckets.iter().zip(takes.iter()).enumerate() {
+            if take == 0 { continue; }
+            match b.kind {
+                BucketKind::Reserve => reserve = add(reserve, take)?,
+                BucketKind::Commission => {
+                    // comissão já sacada pelo consultor não tem como voltar
+                    if Self::commission_funded(env.clone(), r.consultant.clone(), fund_id.clone()) < take { return Err(VaultError::BadStatus); }
+                    Self::bump(env, DataKey::CommissionPool(fund_id.clone()), -take)?;
+                    Self::bump(env, DataKey::CommissionFunded(r.consultant.clone(), fund_id.clone()), -take)?;
+                    Self::bump(env, DataKey::CommissionUnfunded(fund_id.clone()), take)?;
+                }
+                _ => env.events().publish((symbol_short!("wf_rev"), fund_id.clone(), i as u32, b.kind), (b.recipient, take)),
+            }
+        }
+        if reserve > 0 {
+            // a parte já coberta por depósito não volta a ser só registro
+            let free = sub(Self::reserve_balance(env.clone(), fund_id.clone()), Self::reserve_backed(env.clone(), fund_id.clone()))?;
+            if free < reserve { return Err(VaultError::InsufficientReserve); }
+            Self::credit_reserve(env, fund_id, -reserve)?;
+        }
+        Ok(())
     }
-}
+
+    // -------- Reserva de liquidez --------
+    pub fn set_reserve_config(env: Env, fund_id: String, config: ReserveConfig) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        if config.bps as i128 > BPS { return Err(VaultError::Invalid); }
+        if let ReserveBase::Expenses(v) = config.base { if v < 0 { return Err(VaultError::Invalid); } }
+        env.storage().persistent().set(&DataKey::ReserveConfig(fund_id.clone()), &config);
+        env.events().publish((symbol_short!("rsv_cfg"), fund_id), config.bps);
+        Ok(())
+    }
+
+    pub fn reserve_config(env: Env, fund_id: String) -> Option<ReserveConfig> {
+        env.storage().persistent().get(&DataKey::ReserveConfig(fund_id))
+    }
+
+    /// Meta atual da reserva (0 se o fundo não tem meta configurada).
+    pub fn reserve_target(env: Env, fund_id: String) -> Result<i128, VaultError> {
+        let config: ReserveConfig = match env.storage().persistent().get(&DataKey::ReserveConfig(fund_id.clone())) {
+            Some(c) => c,
+            None => return Ok(0),
+        };
+        let base = match config.base {
+            ReserveBase::Expenses(v) => v,
+            ReserveBase::QuotaValue => {
+                let classes = Self::quota_classes(env.clone(), fund_id.clone());
+                if classes.is_empty() {
+                    // supply × preço da cota = NAV (o último marcado); o supply sozinho é
+                    // quantidade, não valor
+                    Self::latest_nav(env.clone(), fund_id).map_or(0, |e| e.nav.max(0))
+                } else {
+                    let mut total: i128 = 0;
+                    for c in classes.iter() { total = add(total, c.principal)?; }
+                    total
+                }
+            }
+        };
+        Ok(base.checked_mul(config.bps as i128).ok_or(VaultError::Math)? / BPS)
+    }
+
+    /// Aporta na reserva a partir da conta `from`, no ativo de pagamento do fundo. O aporte cobre
+    /// primeiro o que a cascata já reteve só em registro; o excedente aumenta o saldo.
+    pub fn fund_reserve(env: Env, from: Address, fund_id: String, amount: i128) -> Result<(), VaultError> {
+        from.require_auth();
+        if amount <= 0 { return Err(VaultError::Invalid); }
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+        token::Client::new(&env, &asset).transfer(&from, &env.current_contract_address(), &amount);
+        let unbacked = sub(Self::reserve_balance(env.clone(), fund_id.clone()), Self::reserve_backed(env.clone(), fund_id.clone()))?;
+        Self::credit_reserve(&env, &fund_id, sub(amount, core::cmp::min(amount, unbacked))?)?;
+        Self::bump(&env, DataKey::ReserveBacked(fund_id.clone()), amount)?;
+        env.events().publish((symbol_short!("rsv_fund"), fund_id, from), amount);
+        Ok(())
+    }
+
+    /// Saque da reserva pelo gestor, sempre com o motivo registrado no evento. Só a parte coberta
+    /// por ativo depositado (`reserve_backed`) pode sair.
+    pub fn withdraw_reserve(env: Env, fund_id: String, amount: i128, to: Address, reason: String) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if amount <= 0 || reason.len() == 0 { return Err(VaultError::Invalid); }
+        let balance = Self::reserve_balance(env.clone(), fund_id.clone());
+        if amount > balance { return Err(VaultError::InsufficientReserve); }
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+        let backed = Self::reserve_backed(env.clone(), fund_id.clone());
+        if amount > backed { return Err(VaultError::InsufficientReserve); }
+
+        env.storage().persistent().set(&DataKey::Reserve(fund_id.clone()), &(balance - amount));
+        env.storage().persistent().set(&DataKey::ReserveBacked(fund_id.clone()), &(backed - amount));
+        token::Client::new(&env, &asset).transfer(&env.current_contract_address(), &to, &amount);
+        env.events().publish((symbol_short!("rsv_wdr"), fund_id, to), (amount, reason));
+        Ok(())
+    }
+
+    fn credit_reserve(env: &Env, fund_id: &String, amount: i128) -> Result<(), VaultError> {
+        let key = DataKey::Reserve(fund_id.clone());
+        let cur: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &add(cur, amount)?);
+        Ok(())
+    }
+
+    // -------- Comissão dos consultores --------
+    /// Comissão padrão do fundo sobre o valor recebido dos títulos originados pelos consultores.
+    pub fn set_commission_rate(env: Env, fund_id: String, bps: u32) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        if bps as i128 > BPS { return Err(VaultError::Invalid); }
+        env.storage().persistent().set(&DataKey::CommissionRate(fund_id.clone()), &bps);
+        env.events().publish((symbol_short!("comm_rate"), fund_id), bps);
+        Ok(())
+    }
+
+    /// Comissão própria do consultor; `None` volta a usar a do fundo.
+    pub fn set_consultant_rate(env: Env, consultant: Address, bps: Option<u32>) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let key = DataKey::ConsultantRate(consultant.clone());
+        match bps {
+            Some(v) => {
+                if v as i128 > BPS { return Err(VaultError::Invalid); }
+                env.storage().persistent().set(&key, &v);
+            }
+            None => env.storage().persistent().remove(&key),
+        }
+        env.events().publish((symbol_short!("comm_rate"), consultant), bps);
+        Ok(())
+    }
+
+    pub fn commission_rate(env: Env, consultant: Address, fund_id: String) -> u32 {
+        env.storage().persistent().get(&DataKey::ConsultantRate(consultant))
+            .or_else(|| env.storage().persistent().get(&DataKey::CommissionRate(fund_id)))
+            .unwrap_or(0)
+    }
+
+    fn accrue_commission(env: &Env, r: &Receivable, paid: i128) -> Result<(), VaultError> {
+        let bps = Self::commission_rate(env.clone(), r.consultant.clone(), r.fund_id.clone());
+        let amount = paid.checked_mul(bps as i128).ok_or(VaultError::Math)? / BPS;
+        if amount == 0 { return Ok(()); }
+        Self::bump(env, DataKey::CommissionDue(r.consultant.clone(), r.fund_id.clone()), amount)?;
+        Self::bump(env, DataKey::CommissionAccrued(r.consultant.clone()), amount)?;
+        Self::bump(env, DataKey::CommissionUnfunded(r.fund_id.clone()), amount)?;
+        env.events().publish((symbol_short!("comm_acc"), r.consultant.clone(), r.id.clone()), amount);
+        Ok(())
+    }
+
+    /// Deposita a partir de `from`, no ativo de pagamento do fundo, o caixa que paga as comissões
+    /// provisionadas pela cascata (a provisão em si é só registro).
+    pub fn fund_commission(env: Env, from: Address, fund_id: String, amount: i128) -> Result<(), VaultError> {
+        from.require_auth();
+        if amount <= 0 { return Err(VaultError::Invalid); }
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+        token::Client::new(&env, &asset).transfer(&from, &env.current_contract_address(), &amount);
+        Self::bump(&env, DataKey::CommissionBacked(fund_id.clone()), amount)?;
+        env.events().publish((symbol_short!("comm_fund"), fund_id, from), amount);
+        Ok(())
+    }
+
+    /// Saca a comissão do consultor no fundo, até o que a faixa `Commission` da cascata já
+    /// provisionou para ele (nos pagamentos dos títulos que ele originou) e até o caixa depositado
+    /// em `fund_commission`.
+    pub fn claim_commission(env: Env, consultant: Address, fund_id: String) -> Result<i128, VaultError> {
+        consultant.require_auth();
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+        let funded = Self::commission_funded(env.clone(), consultant.clone(), fund_id.clone());
+        let amount = core::cmp::min(funded, Self::commission_backed(env.clone(), fund_id.clone()));
+        if amount > 0 {
+            Self::bump(&env, DataKey::CommissionDue(consultant.clone(), fund_id.clone()), -amount)?;
+            Self::bump(&env, DataKey::CommissionAccrued(consultant.clone()), -amount)?;
+            Self::bump(&env, DataKey::CommissionPool(fund_id.clone()), -amount)?;
+            Self::bump(&env, DataKey::CommissionBacked(fund_id.clone()), -amount)?;
+            let key = DataKey::CommissionFunded(consultant.clone(), fund_id.clone());
+            if amount == funded { env.storage().persistent().remove(&key); } else { env.storage().persistent().set(&key, &(funded - amount)); }
+            token::Client::new(&env, &asset).transfer(&env.current_contract_address(), &consultant, &amount);
+            env.events().publish((symbol_short!("comm_pay"), fund_id, consultant), amount);
+        }
+        Ok(amount)
+    }
+
+    /// Comissão acumulada e ainda não sacada, somando todos os fundos.
+    pub fn consultant_accrued(env: Env, addr: Address) -> i128 {
+        env.storage().persistent().get(&DataKey::CommissionAccrued(addr)).unwrap_or(0)
+    }
+    pub fn commission_due(env: Env, consultant: Address, fund_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::CommissionDue(consultant, fund_id)).unwrap_or(0)
+    }
+    pub fn commission_pool(env: Env, fund_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::CommissionPool(fund_id)).unwrap_or(0)
+    }
+    pub fn commission_backed(env: Env, fund_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::CommissionBacked(fund_id)).unwrap_or(0)
+    }
+    pub fn commission_funded(env: Env, consultant: Address, fund_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::CommissionFunded(consultant, fund_id)).unwrap_or(0)
+    }
+
+    /// Soma `delta` a um contador i128 persistente.
+    fn bump(env: &Env, key: DataKey, delta: i128) -> Result<(), VaultError> {
+        let cur: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &add(cur, delta)?);
+        Ok(())
+    }
+
+    /// Valida a lista de cotistas e calcula as partes; não grava nada.
+    fn compute_shares<'a>(env: &'a Env, fund_id: &String, holders: &Vec<Address>, paid: i128) -> Result<(FundTokenClient<'a>, Vec<i128>, Vec<i128>), VaultError> {
+        // pega o token do fundo
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        let token = FundTokenClient::new(env, &fund.token);
+
+        // soma saldos
+        let mut total_shares: i128 = 0;
+        let mut balances: Vec<i128> = vec![env];
+        let mut seen: Map<Address, bool> = Map::new(env);
+
+        for h in holders.iter() {
+            if seen.contains_key(h.clone()) { return Err(VaultError::DuplicateHolder); }
+            seen.set(h.clone(), true);
+            // cross-contract call: FundToken::balance(env, h)
+            let bal = token.balance(&h);
+            balances.push_back(bal);
+            total_shares = add(total_shares, bal)?;
+        }
+        if total_shares <= 0 { return Err(VaultError::Invalid); }
+        // a lista tem que cobrir todo o supply, senão quem ficou de fora perde a sua parte
+        if total_shares != token.total_supply() { return Err(VaultError::IncompleteHolders); }
+
+        // distribuição (já com o resíduo pelo maior resto)
+        let (shares, extra) = allocate(env, &balances, paid)?;
+        Ok((token, shares, extra))
+    }
+
+    /// Paga `amount` ao cotista, ou aplica a `HolderPolicy` do fundo se ele não está na whitelist.
+    fn pay_holder(
+        env: &Env,
+        fund_id: &String,
+        token: &FundTokenClient,
+        policy: &HolderPolicy,
+        holder: Address,
+        amount: i128,
+        topic: Symbol,
+    ) -> Result<(), VaultError> {
+        if token.is_whitelisted(&holder) {
+            env.events().publish((topic, fund_id.clone(), holder), amount);
+            return Ok(());
+        }
+        match policy {
+            HolderPolicy::Escrow => {
+                let key = DataKey::Escrow(fund_id.clone(), holder.clone());
+                let now = env.ledger().timestamp();
+                let deadline = Self::claim_deadline(env, fund_id, now);
+                let mut e: UnclaimedBalance = match env.storage().persistent().get(&key) {
+                    Some(e) => e,
+                    None => {
+                        let mut list: Vec<Address> = env.storage().persistent().get(&DataKey::EscrowHolders(fund_id.clone())).unwrap_or(vec![env]);
+                        list.push_back(holder.clone());
+                        env.storage().persistent().set(&DataKey::EscrowHolders(fund_id.clone()), &list);
+                        UnclaimedBalance { holder: holder.clone(), amount: 0, since: now, expires_at: deadline, lots: vec![env] }
+                    }
+                };
+                // cada retenção tem o seu prazo; com o limite de lotes cheio, junta na última
+                // (e fica com o prazo mais longo)
+                match e.lots.last() {
+                    Some(mut last) if last.expires_at == deadline || e.lots.len() >= MAX_ESCROW_LOTS => {
+                        last.amount = add(last.amount, amount)?;
+                        last.expires_at = core::cmp::max(last.expires_at, deadline);
+                        e.lots.set(e.lots.len() - 1, last);
+                    }
+                    _ => e.lots.push_back(EscrowLot { amount, expires_at: deadline }),
+                }
+                Self::summarize_escrow(&mut e)?;
+                env.storage().persistent().set(&key, &e);
+                env.events().publish((symbol_short!("dist_escr"), fund_id.clone(), holder), amount);
+            }
+            HolderPolicy::Skip => {
+                let key = DataKey::Unallocated(fund_id.clone());
+                let cur: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+                env.storage().persistent().set(&key, &add(cur, amount)?);
+                env.events().publish((symbol_short!("dist_skip"), fund_id.clone(), holder), amount);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_holder_policy(env: Env, fund_id: String, policy: HolderPolicy) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        env.storage().persistent().set(&DataKey::HolderPolicy(fund_id.clone()), &policy);
+        env.events().publish((symbol_short!("policy"), fund_id), policy);
+        Ok(())
+    }
+
+    /// Libera o valor retido ainda no prazo de um cotista que voltou à whitelist; lotes vencidos
+    /// ficam para `sweep_unclaimed`. Retorna o valor pago.
+    pub fn release_escrow(env: Env, fund_id: String, holder: Address) -> Result<i128, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let key = DataKey::Escrow(fund_id.clone(), holder.clone());
+        let mut e: UnclaimedBalance = env.storage().persistent().get(&key).ok_or(VaultError::NotFound)?;
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        if !FundTokenClient::new(&env, &fund.token).is_whitelisted(&holder) { return Err(VaultError::NotWhitelisted); }
+        let paid = Self::take_lots(&env, &mut e, false)?;
+        if paid == 0 { return Err(VaultError::Expired); }
+
+        if e.lots.is_empty() {
+            Self::remove_escrow(&env, &fund_id, &holder);
+        } else {
+            env.storage().persistent().set(&key, &e);
+        }
+        env.events().publish((symbol_short!("esc_rel"), fund_id, holder), paid);
+        Ok(paid)
+    }
+
+    /// Retira de `e` os lotes vencidos (`expired`) ou no prazo e retorna a soma retirada.
+    fn take_lots(env: &Env, e: &mut UnclaimedBalance, expired: bool) -> Result<i128, VaultError> {
+        let now = env.ledger().timestamp();
+        let mut kept: Vec<EscrowLot> = vec![env];
+        let mut taken: i128 = 0;
+        for lot in e.lots.iter() {
+            if (now > lot.expires_at) == expired { taken = add(taken, lot.amount)?; } else { kept.push_back(lot); }
+        }
+        e.lots = kept;
+        Self::summarize_escrow(e)?;
+        Ok(taken)
+    }
+
+    fn summarize_escrow(e: &mut UnclaimedBalance) -> Result<(), VaultError> {
+        e.amount = 0;
+        e.expires_at = u64::MAX;
+        for lot in e.lots.iter() {
+            e.amount = add(e.amount, lot.amount)?;
+            e.expires_at = core::cmp::min(e.expires_at, lot.expires_at);
+        }
+        Ok(())
+    }
+
+    fn remove_escrow(env: &Env, fund_id: &String, holder: &Address) {
+        env.storage().persistent().remove(&DataKey::Escrow(fund_id.clone(), holder.clone()));
+        let mut list: Vec<Address> = env.storage().persistent().get(&DataKey::EscrowHolders(fund_id.clone())).unwrap_or(vec![env]);
+        if let Some(i) = list.first_index_of(holder.clone()) { list.remove(i); }
+        env.storage().persistent().set(&DataKey::EscrowHolders(fund_id.clone()), &list);
+    }
+
+    fn claim_deadline(env: &Env, fund_id: &String, since: u64) -> u64 {
+        match env.storage().persistent().get::<_, u64>(&DataKey::ClaimWindow(fund_id.clone())) {
+            Some(w) => since.saturating_add(w),
+            None => u64::MAX,
+        }
+    }
+
+    /// Prazo, em segundos, para o cotista receber valores retidos (contado de cada retenção) e
+    /// sacar rendimentos (contado de cada lançamento). Para retenções vale só nas novas; para
+    /// rendimentos, `sweep_rewards` usa o prazo vigente.
+    pub fn set_claim_window(env: Env, fund_id: String, seconds: u64) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if seconds == 0 { return Err(VaultError::Invalid); }
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        env.storage().persistent().set(&DataKey::ClaimWindow(fund_id.clone()), &seconds);
+        env.events().publish((symbol_short!("claim_win"), fund_id), seconds);
+        Ok(())
+    }
+
+    /// Recolhe os lotes vencidos dos cotistas nas posições [start, start + limit) da lista de
+    /// retenções, para a reserva do fundo ou para redistribuição. Emite um evento por cotista e
+    /// retorna (total recolhido, próximo `start`); quem fica sem lotes sai da lista.
+    pub fn sweep_unclaimed(env: Env, fund_id: String, start: u32, limit: u32, target: SweepTarget) -> Result<(i128, u32), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if limit == 0 { return Err(VaultError::Invalid); }
+        if limit > MAX_BATCH { return Err(VaultError::BatchTooLarge); }
+
+        let mut list: Vec<Address> = env.storage().persistent().get(&DataKey::EscrowHolders(fund_id.clone())).unwrap_or(vec![&env]);
+        let end = core::cmp::min(start.saturating_add(limit), list.len());
+        let mut total: i128 = 0;
+        let mut removed: u32 = 0;
+        // de trás para frente: remover da lista não desloca as posições ainda não vistas
+        for pos in (start..end).rev() {
+            let holder = list.get_unchecked(pos);
+            let key = DataKey::Escrow(fund_id.clone(), holder.clone());
+            let mut e: UnclaimedBalance = env.storage().persistent().get(&key).unwrap();
+            let swept = Self::take_lots(&env, &mut e, true)?;
+            if swept == 0 { continue; }
+            if e.lots.is_empty() {
+                env.storage().persistent().remove(&key);
+                list.remove(pos);
+                removed += 1;
+            } else {
+                env.storage().persistent().set(&key, &e);
+            }
+            total = add(total, swept)?;
+            env.events().publish((symbol_short!("swept"), fund_id.clone(), holder), swept);
+        }
+        let next = end.saturating_sub(removed);
+        if total == 0 { return Ok((0, next)); }
+        env.storage().persistent().set(&DataKey::EscrowHolders(fund_id.clone()), &list);
+
+        match target.clone() {
+            SweepTarget::Reserve => {
+                // o valor retido já está no vault: entra na reserva coberto
+                Self::credit_reserve(&env, &fund_id, total)?;
+                Self::bump(&env, DataKey::ReserveBacked(fund_id.clone()), total)?;
+            }
+            SweepTarget::Redistribute(from) => {
+                let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+                let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+                from.require_auth();
+                token::Client::new(&env, &asset).transfer(&from, &env.current_contract_address(), &total);
+                Self::launch_reward(&env, &fund.token, total);
+                Self::bump(&env, DataKey::Claimable(fund_id.clone()), total)?;
+            }
+        }
+        env.events().publish((symbol_short!("sweep_sum"), fund_id, target), total);
+        Ok((total, next))
+    }
+
+    /// Prescreve o rendimento de `holders` lançado há mais que o prazo do fundo
+    /// (`set_claim_window`) e ainda não sacado. Com `Reserve` o valor sai do caixa de rendimentos
+    /// para a reserva; com `Redistribute` é relançado no mesmo FundToken, sem novo depósito (o
+    /// valor já está no caixa). Retorna o total prescrito.
+    pub fn sweep_rewards(env: Env, fund_id: String, holders: Vec<Address>, target: SweepTarget) -> Result<i128, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if holders.len() > MAX_BATCH { return Err(VaultError::BatchTooLarge); }
+        let window: u64 = env.storage().persistent().get(&DataKey::ClaimWindow(fund_id.clone())).ok_or(VaultError::Invalid)?;
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        let cutoff = env.ledger().timestamp().saturating_sub(window);
+
+        let mut total: i128 = 0;
+        for t in Self::fund_tokens(&env, &fund).iter() {
+            let Some(mark) = Self::reward_mark_before(&env, &t, cutoff) else { continue };
+            let fund_token = FundTokenClient::new(&env, &t);
+            let mut swept: i128 = 0;
+            for h in holders.iter() {
+                let v = fund_token.expire_reward(&h, &mark);
+                if v > 0 {
+                    swept = add(swept, v)?;
+                    env.events().publish((symbol_short!("rwd_swept"), fund_id.clone(), h), v);
+                }
+            }
+            if swept > 0 {
+                if let SweepTarget::Redistribute(_) = target { Self::launch_reward(&env, &t, swept); }
+                total = add(total, swept)?;
+            }
+        }
+        if total == 0 { return Ok(0); }
+        if let SweepTarget::Reserve = target {
+            Self::bump(&env, DataKey::Claimable(fund_id.clone()), -total)?;
+            Self::credit_reserve(&env, &fund_id, total)?;
+            Self::bump(&env, DataKey::ReserveBacked(fund_id.clone()), total)?;
+        }
+        env.events().publish((symbol_short!("rwd_sweep"), fund_id, target), total);
+        Ok(total)
+    }
+
+    /// Lança `amount` no acumulador do FundToken e registra (instante, RewardPerShare) para a
+    /// prescrição em `sweep_rewards`.
+    fn launch_reward(env: &Env, token: &Address, amount: i128) {
+        let fund_token = FundTokenClient::new(env, token);
+        fund_token.add_reward(&amount);
+        let n: u32 = env.storage().persistent().get(&DataKey::RewardMarkCount(token.clone())).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::RewardMark(token.clone(), n), &(env.ledger().timestamp(), fund_token.reward_per_share()));
+        env.storage().persistent().set(&DataKey::RewardMarkCount(token.clone()), &(n + 1));
+    }
+
+    /// RewardPerShare depois do último lançamento anterior a `cutoff` (busca binária).
+    fn reward_mark_before(env: &Env, token: &Address, cutoff: u64) -> Option<i128> {
+        let (mut lo, mut hi): (u32, u32) = (0, env.storage().persistent().get(&DataKey::RewardMarkCount(token.clone())).unwrap_or(0));
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let (at, _): (u64, i128) = env.storage().persistent().get(&DataKey::RewardMark(token.clone(), mid)).unwrap();
+            if at < cutoff { lo = mid + 1; } else { hi = mid; }
+        }
+        if lo == 0 { return None; }
+        env.storage().persistent().get::<_, (u64, i128)>(&DataKey::RewardMark(token.clone(), lo - 1)).map(|(_, rps)| rps)
+    }
+
+    /// Valores retidos em aberto por cotista, com o prazo de cada um.
+    pub fn unclaimed_balances(env: Env, fund_id: String) -> Vec<UnclaimedBalance> {
+        let list: Vec<Address> = env.storage().persistent().get(&DataKey::EscrowHolders(fund_id.clone())).unwrap_or(vec![&env]);
+        let mut out: Vec<UnclaimedBalance> = vec![&env];
+        for holder in list.iter() {
+            out.push_back(env.storage().persistent().get(&DataKey::Escrow(fund_id.clone(), holder)).unwrap());
+        }
+        out
+    }
+
+    pub fn holder_policy(env: Env, fund_id: String) -> HolderPolicy {
+        env.storage().persistent().get(&DataKey::HolderPolicy(fund_id)).unwrap_or(HolderPolicy::Escrow)
+    }
+    pub fn escrowed(env: Env, fund_id: String, holder: Address) -> i128 {
+        env.storage().persistent().get::<_, UnclaimedBalance>(&DataKey::Escrow(fund_id, holder)).map(|e| e.amount).unwrap_or(0)
+    }
+    pub fn reserve_balance(env: Env, fund_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::Reserve(fund_id)).unwrap_or(0)
+    }
+    /// Parte de `reserve_balance` coberta por ativo que o vault de fato recebeu em `fund_reserve`.
+    pub fn reserve_backed(env: Env, fund_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::ReserveBacked(fund_id)).unwrap_or(0)
+    }
+    pub fn unallocated(env: Env, fund_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::Unallocated(fund_id)).unwrap_or(0)
+    }
+
+    // -------- Distribuição por saque (pull) --------
+    // Cada pagamento é lançado em O(1) no acumulador de rendimento por cota do FundToken;
+    // o token liquida o cotista a cada mudança de saldo e o cotista saca com `claim`.
+
+    /// Define o ativo de pagamento do fundo. Trocar um ativo já definido só via timelock.
+    pub fn set_payment_asset(env: Env, fund_id: String, asset: Address) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if env.storage().persistent().has(&DataKey::PaymentAsset(fund_id.clone())) { return Err(VaultError::Timelocked); }
+        Self::write_payment_asset(&env, fund_id, asset)
+    }
+
+    fn write_payment_asset(env: &Env, fund_id: String, asset: Address) -> Result<(), VaultError> {
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        env.storage().persistent().set(&DataKey::PaymentAsset(fund_id.clone()), &asset);
+        env.events().publish((symbol_short!("pay_asset"), fund_id), asset);
+        Ok(())
+    }
+
+    /// Paga a cascata e lança o líquido do recebível no acumulador do fundo. O vault precisa ser o
+    /// distributor do FundToken; `from` deposita o líquido no ativo de pagamento.
+    pub fn distribute_claimable(env: Env, from: Address, receivable_id: String) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if Self::needs_proposal(&env, &Self::receivable_fund(&env, &receivable_id)?, None) { return Err(VaultError::NeedsProposal); }
+        Self::launch_claimable(env, from, receivable_id)
+    }
+
+    fn launch_claimable(env: Env, from: Address, receivable_id: String) -> Result<(), VaultError> {
+        let mut r = Self::distributable(&env, &receivable_id)?;
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(r.fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(r.fund_id.clone())).ok_or(VaultError::NotFound)?;
+        from.require_auth();
+        let net = Self::pay_waterfall(&env, &r, r.paid_amount)?;
+        if net > 0 {
+            // o acumulador só cresce com o que entrou no vault
+            token::Client::new(&env, &asset).transfer(&from, &env.current_contract_address(), &net);
+            let mut classes = Self::quota_classes(env.clone(), r.fund_id.clone());
+            if classes.is_empty() {
+                Self::launch_reward(&env, &fund.token, net);
+            } else {
+                // sênior até o benchmark, depois mezanino, o resto para a subordinada
+                let amounts = Self::split_classes(&env, &mut classes, net)?;
+                for (c, amount) in classes.iter().zip(amounts.iter()) {
+                    if amount > 0 { Self::launch_reward(&env, &c.token, amount); }
+                    env.events().publish((symbol_short!("class_pay"), r.fund_id.clone(), c.kind), amount);
+                }
+                env.storage().persistent().set(&DataKey::QuotaClasses(r.fund_id.clone()), &classes);
+                Self::check_subordination(&env, &r.fund_id, &classes);
+            }
+            let key = DataKey::Claimable(r.fund_id.clone());
+            let cur: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+            env.storage().persistent().set(&key, &add(cur, net)?);
+        }
+
+        r.status = ReceivableStatus::Distributed;
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id.clone()), &r);
+        env.events().publish((symbol_short!("dist_sum"), r.fund_id, receivable_id), r.paid_amount);
+        Ok(())
+    }
+
+    /// Saca o rendimento acumulado do cotista no ativo de pagamento do fundo. Retorna o valor pago.
+    pub fn claim(env: Env, investor: Address, fund_id: String) -> Result<i128, VaultError> {
+        investor.require_auth();
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+
+        let mut amount: i128 = 0;
+        for t in Self::fund_tokens(&env, &fund).iter() {
+            let fund_token = FundTokenClient::new(&env, &t);
+            if fund_token.pending_reward(&investor) == 0 { continue; }
+            if !fund_token.is_whitelisted(&investor) { return Err(VaultError::NotWhitelisted); }
+            amount = add(amount, fund_token.take_reward(&investor))?;
+        }
+        if amount > 0 {
+            token::Client::new(&env, &asset).transfer(&env.current_contract_address(), &investor, &amount);
+            let key = DataKey::Claimable(fund_id.clone());
+            let cur: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+            env.storage().persistent().set(&key, &sub(cur, amount)?);
+            env.events().publish((symbol_short!("claim"), fund_id, investor), amount);
+        }
+        Ok(amount)
+    }
+
+    pub fn claimable(env: Env, fund_id: String, investor: Address) -> Result<i128, VaultError> {
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id)).ok_or(VaultError::NotFound)?;
+        let mut total: i128 = 0;
+        for t in Self::fund_tokens(&env, &fund).iter() {
+            total = add(total, FundTokenClient::new(&env, &t).pending_reward(&investor))?;
+        }
+        Ok(total)
+    }
+    pub fn total_claimable(env: Env, fund_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::Claimable(fund_id)).unwrap_or(0)
+    }
+    pub fn payment_asset(env: Env, fund_id: String) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::PaymentAsset(fund_id))
+    }
+
+    // -------- Classes de cotas --------
+    // Sênior e mezanino recebem até o benchmark (com o que faltou acumulado para os próximos
+    // pagamentos); a subordinada fica com o resto e é a primeira a absorver perdas. Cada classe
+    // tem o seu FundToken e os fundos com classes distribuem pelo modelo pull (`distribute_claimable`).
+
+    /// `classes` em ordem: sênior, mezanino (opcional), subordinada, no máximo uma de cada.
+    pub fn set_quota_classes(env: Env, fund_id: String, classes: Vec<QuotaClass>) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        if classes.len() < 2 { return Err(VaultError::Invalid); }
+
+        let now = env.ledger().timestamp();
+        let old = Self::quota_classes(env.clone(), fund_id.clone());
+        let mut out: Vec<QuotaClass> = vec![&env];
+        let mut prev: Option<QuotaClassKind> = None;
+        for mut c in classes.iter() {
+            if let Some(p) = prev { if c.kind <= p { return Err(VaultError::Invalid); } }
+            if c.principal < 0 { return Err(VaultError::Invalid); }
+            prev = Some(c.kind);
+            // reconfigurar não apaga o histórico: perdas e remuneração devida (apurada até agora
+            // com os parâmetros antigos) continuam com a classe do mesmo tipo
+            c.losses = 0;
+            c.shortfall = 0;
+            if let Some(o) = old.iter().find(|o| o.kind == c.kind) {
+                c.losses = o.losses;
+                c.shortfall = add(o.shortfall, benchmark_accrued(&o, now)?)?;
+            }
+            c.last_accrual = now;
+            out.push_back(c);
+        }
+        if prev != Some(QuotaClassKind::Subordinated) { return Err(VaultError::Invalid); }
+
+        // tokens que saíram das classes deixam de apontar para o fundo
+        for o in old.iter() {
+            env.storage().persistent().remove(&DataKey::ClassToken(o.token));
+        }
+        for c in out.iter() {
+            env.storage().persistent().set(&DataKey::ClassToken(c.token), &fund_id);
+        }
+        env.storage().persistent().set(&DataKey::QuotaClasses(fund_id.clone()), &out);
+        env.events().publish((symbol_short!("classes"), fund_id), out.len());
+        Ok(())
+    }
+
+    pub fn quota_classes(env: Env, fund_id: String) -> Vec<QuotaClass> {
+        env.storage().persistent().get(&DataKey::QuotaClasses(fund_id)).unwrap_or(vec![&env])
+    }
+
+    /// Quanto cada classe receberia de `amount` agora (mesma conta de `distribute_claimable`).
+    pub fn class_split(env: Env, fund_id: String, amount: i128) -> Result<Vec<(QuotaClassKind, i128)>, VaultError> {
+        let mut classes = Self::quota_classes(env.clone(), fund_id);
+        let amounts = Self::split_classes(&env, &mut classes, amount)?;
+        let mut out = vec![&env];
+        for (c, a) in classes.iter().zip(amounts.iter()) { out.push_back((c.kind, a)); }
+        Ok(out)
+    }
+
+    /// Reparte `amount` entre as classes e atualiza benchmark devido/pago em `classes`.
+    fn split_classes(env: &Env, classes: &mut Vec<QuotaClass>, amount: i128) -> Result<Vec<i128>, VaultError> {
+        let now = env.ledger().timestamp();
+        let mut rest = amount;
+        let mut out: Vec<i128> = vec![env];
+        for i in 0..classes.len() {
+            let mut c = classes.get(i).unwrap();
+            let pay = if let QuotaClassKind::Subordinated = c.kind {
+                rest
+            } else {
+                let due = add(c.shortfall, benchmark_accrued(&c, now)?)?;
+                let pay = core::cmp::min(due, rest);
+                c.shortfall = sub(due, pay)?;
+                pay
+            };
+            c.last_accrual = now;
+            rest = sub(rest, pay)?;
+            out.push_back(pay);
+            classes.set(i, c);
+        }
+        Ok(out)
+    }
+
+    /// Baixa por inadimplência: o valor em aberto vira perda, absorvida da subordinada para cima.
+    pub fn write_off(env: Env, receivable_id: String) -> Result<i128, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let mut r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if let ReceivableStatus::Pending = r.status {} else { return Err(VaultError::BadStatus); }
+        let loss = Self::outstanding(env.clone(), receivable_id.clone())?;
+
+        r.status = ReceivableStatus::WrittenOff;
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id.clone()), &r);
+        // parcelas em aberto também saem: um pagamento posterior não reabre o título
+        if let ReceivableKind::Installments = r.kind {
+            let key = DataKey::Installments(receivable_id.clone());
+            let mut installments: Vec<Installment> = env.storage().persistent().get(&key).ok_or(VaultError::NotFound)?;
+            for i in 0..installments.len() {
+                let mut inst = installments.get_unchecked(i);
+                if let ReceivableStatus::Pending = inst.status {
+                    inst.status = ReceivableStatus::WrittenOff;
+                    installments.set(i, inst);
+                }
+            }
+            env.storage().persistent().set(&key, &installments);
+        }
+
+        let mut classes = Self::quota_classes(env.clone(), r.fund_id.clone());
+        let mut rest = loss;
+        for i in (0..classes.len()).rev() {
+            if rest == 0 { break; }
+            let mut c = classes.get(i).unwrap();
+            let absorbed = core::cmp::min(rest, c.principal);
+            if absorbed == 0 { continue; }
+            c.principal = sub(c.principal, absorbed)?;
+            c.losses = add(c.losses, absorbed)?;
+            rest = sub(rest, absorbed)?;
+            env.events().publish((symbol_short!("loss"), r.fund_id.clone(), c.kind), absorbed);
+            classes.set(i, c);
+        }
+        if !classes.is_empty() {
+            env.storage().persistent().set(&DataKey::QuotaClasses(r.fund_id.clone()), &classes);
+            Self::check_subordination(&env, &r.fund_id, &classes);
+        }
+        env.events().publish((symbol_short!("write_off"), r.fund_id, receivable_id), loss);
+        Ok(loss)
+    }
+
+    // -------- Razão de subordinação --------
+    pub fn set_min_subordination(env: Env, fund_id: String, bps: u32) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if bps as i128 > BPS { return Err(VaultError::Invalid); }
+        if !env.storage().persistent().has(&DataKey::QuotaClasses(fund_id.clone())) { return Err(VaultError::NoClasses); }
+        env.storage().persistent().set(&DataKey::MinSubordination(fund_id.clone()), &bps);
+        env.events().publish((symbol_short!("min_sub"), fund_id), bps);
+        Ok(())
+    }
+
+    /// Principal da subordinada sobre o principal de todas as classes, em bps.
+    pub fn subordination_ratio(env: Env, fund_id: String) -> Result<u32, VaultError> {
+        let classes = Self::quota_classes(env.clone(), fund_id);
+        if classes.is_empty() { return Err(VaultError::NoClasses); }
+        Self::ratio_of(&classes)
+    }
+
+    pub fn min_subordination(env: Env, fund_id: String) -> u32 {
+        env.storage().persistent().get(&DataKey::MinSubordination(fund_id)).unwrap_or(0)
+    }
+
+    fn ratio_of(classes: &Vec<QuotaClass>) -> Result<u32, VaultError> {
+        let mut total: i128 = 0;
+        let mut subordinated: i128 = 0;
+        for c in classes.iter() {
+            total = add(total, c.principal)?;
+            if let QuotaClassKind::Subordinated = c.kind { subordinated = c.principal; }
+        }
+        if total == 0 { return Ok(BPS as u32); }
+        Ok((subordinated.checked_mul(BPS).ok_or(VaultError::Math)? / total) as u32)
+    }
+
+    /// Emite `sub_alert` se a razão ficou abaixo do mínimo. Retorna se está enquadrado.
+    fn check_subordination(env: &Env, fund_id: &String, classes: &Vec<QuotaClass>) -> bool {
+        let min = Self::min_subordination(env.clone(), fund_id.clone());
+        let ratio = Self::ratio_of(classes).unwrap_or(0);
+        if ratio < min {
+            env.events().publish((symbol_short!("sub_alert"), fund_id.clone()), (ratio, min));
+            return false;
+        }
+        true
+    }
+
+    /// Chamado pelo FundToken de uma classe a cada `mint` (ver `set_mint_guard` no token).
+    /// Soma a emissão ao principal da classe e recusa emissões sênior/mezanino que deixariam a
+    /// razão de subordinação abaixo do mínimo, emitindo `sub_alert` (o `mint` do token então
+    /// retorna false sem reverter). `supply` é o total_supply do token antes da emissão.
+    pub fn on_mint(env: Env, token: Address, quotas: i128, supply: i128) -> bool {
+        token.require_auth();
+        let Some((fund_id, classes, kind)) = Self::project_mint(&env, &token, quotas, supply) else { return true };
+        if kind != QuotaClassKind::Subordinated && !Self::check_subordination(&env, &fund_id, &classes) { return false; }
+        env.storage().persistent().set(&DataKey::QuotaClasses(fund_id), &classes);
+        true
+    }
+
+    /// Simula a emissão de `quotas` no token da classe, sem gravar nada. Se ela desenquadraria o
+    /// fundo, emite `sub_alert` (numa transação que não reverte) e retorna false.
+    pub fn check_mint(env: Env, token: Address, quotas: i128) -> bool {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let supply = FundTokenClient::new(&env, &token).total_supply();
+        let Some((fund_id, classes, kind)) = Self::project_mint(&env, &token, quotas, supply) else { return true };
+        kind == QuotaClassKind::Subordinated || Self::check_subordination(&env, &fund_id, &classes)
+    }
+
+    /// Classes do fundo com a emissão somada ao principal da classe de `token` (None se o token
+    /// não é de classe). A cota vale principal / supply; classe sem cotas ou sem principal usa o
+    /// preço inicial da subscrição (ao par, sem termos).
+    fn project_mint(env: &Env, token: &Address, quotas: i128, supply: i128) -> Option<(String, Vec<QuotaClass>, QuotaClassKind)> {
+        let fund_id: String = env.storage().persistent().get(&DataKey::ClassToken(token.clone()))?;
+        let mut classes = Self::quota_classes(env.clone(), fund_id.clone());
+        let idx = classes.iter().position(|c| c.token == *token)? as u32;
+        let mut c = classes.get(idx).unwrap();
+        let value = if supply > 0 && c.principal > 0 {
+            quotas.saturating_mul(c.principal) / supply
+        } else {
+            let price = env.storage().persistent().get::<_, SubscriptionTerms>(&DataKey::SubscriptionTerms(fund_id.clone()))
+                .map_or(PRICE_SCALE, |t| t.initial_price);
+            quotas.saturating_mul(price) / PRICE_SCALE
+        };
+        c.principal = c.principal.saturating_add(value);
+        let kind = c.kind;
+        classes.set(idx, c);
+        Some((fund_id, classes, kind))
+    }
+
+    /// FundTokens do fundo: um por classe, ou o token único do FundRecord.
+    fn fund_tokens(env: &Env, fund: &FundRecord) -> Vec<Address> {
+        let classes = Self::quota_classes(env.clone(), fund.id.clone());
+        if classes.is_empty() { return vec![env, fund.token.clone()]; }
+        let mut out = vec![env];
+        for c in classes.iter() { out.push_back(c.token); }
+        out
+    }
+
+    fn ensure_single_class(env: &Env, fund_id: &String) -> Result<(), VaultError> {
+        if env.storage().persistent().has(&DataKey::QuotaClasses(fund_id.clone())) { return Err(VaultError::ClassedFund); }
+        Ok(())
+    }
+
+    // -------- Distribuição por raiz de Merkle --------
+    // Alternativa para bases muito grandes: o gestor publica só a raiz; cada cotista prova a
+    // sua folha (cotista, valor). `dist_id` identifica o recebível ou o período.
+
+    /// Publica a raiz e transfere `total` de `from` para o vault no mesmo ato, de modo que os
+    /// saques só consomem o que foi depositado para esta distribuição.
+    pub fn publish_merkle_root(
+        env: Env,
+        from: Address,
+        dist_id: String,
+        fund_id: String,
+        receivable_id: Option<String>,
+        root: BytesN<32>,
+        total: i128,
+        deadline: u64,
+    ) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if Self::needs_proposal(&env, &fund_id, None) { return Err(VaultError::NeedsProposal); }
+        Self::publish_root(env, MerklePublication { from, dist_id, fund_id, receivable_id, root, total, deadline })
+    }
+
+    fn publish_root(env: Env, m: MerklePublication) -> Result<(), VaultError> {
+        let MerklePublication { from, dist_id, fund_id, receivable_id, root, total, deadline } = m;
+        if total <= 0 || deadline <= env.ledger().timestamp() { return Err(VaultError::Invalid); }
+        if env.storage().persistent().has(&DataKey::MerkleDist(dist_id.clone())) { return Err(VaultError::DuplicateId); }
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+        Self::ensure_single_class(&env, &fund_id)?;
+        from.require_auth();
+
+        if let Some(rid) = receivable_id.clone() {
+            let mut r = Self::distributable(&env, &rid)?;
+            if r.fund_id != fund_id { return Err(VaultError::Invalid); }
+            // a árvore cobre só o líquido da cascata
+            if Self::pay_waterfall(&env, &r, r.paid_amount)? != total { return Err(VaultError::Invalid); }
+            r.status = ReceivableStatus::Distributed;
+            env.storage().persistent().set(&DataKey::Receivable(rid), &r);
+        }
+
+        token::Client::new(&env, &asset).transfer(&from, &env.current_contract_address(), &total);
+        let d = MerkleDistribution { fund_id: fund_id.clone(), receivable_id, root: root.clone(), total, claimed: 0, deadline, swept: false };
+        env.storage().persistent().set(&DataKey::MerkleDist(dist_id.clone()), &d);
+        env.events().publish((symbol_short!("mrkl_pub"), fund_id, dist_id), root);
+        Ok(())
+    }
+
+    pub fn claim_merkle(env: Env, investor: Address, dist_id: String, amount: i128, proof: Vec<BytesN<32>>) -> Result<i128, VaultError> {
+        investor.require_auth();
+        let mut d: MerkleDistribution = env.storage().persistent().get(&DataKey::MerkleDist(dist_id.clone())).ok_or(VaultError::NotFound)?;
+        if env.ledger().timestamp() > d.deadline || d.swept { return Err(VaultError::Expired); }
+        let claimed_key = DataKey::MerkleClaimed(dist_id.clone(), investor.clone());
+        if env.storage().persistent().has(&claimed_key) { return Err(VaultError::AlreadyClaimed); }
+
+        let mut node = merkle_leaf(&env, &investor, amount);
+        for p in proof.iter() { node = merkle_node(&env, &node, &p); }
+        if node != d.root { return Err(VaultError::InvalidProof); }
+
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(d.fund_id.clone())).ok_or(VaultError::NotFound)?;
+        if !FundTokenClient::new(&env, &fund.token).is_whitelisted(&investor) { return Err(VaultError::NotWhitelisted); }
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(d.fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+
+        d.claimed = add(d.claimed, amount)?;
+        if d.claimed > d.total { return Err(VaultError::Math); }
+        env.storage().persistent().set(&claimed_key, &true);
+        env.storage().persistent().set(&DataKey::MerkleDist(dist_id.clone()), &d);
+        token::Client::new(&env, &asset).transfer(&env.current_contract_address(), &investor, &amount);
+        env.events().publish((symbol_short!("mrkl_clm"), dist_id, investor), amount);
+        Ok(amount)
+    }
+
+    /// Depois do prazo, recolhe o que não foi sacado para `to`. Retorna o valor recolhido.
+    pub fn sweep_merkle(env: Env, dist_id: String, to: Address) -> Result<i128, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let mut d: MerkleDistribution = env.storage().persistent().get(&DataKey::MerkleDist(dist_id.clone())).ok_or(VaultError::NotFound)?;
+        if env.ledger().timestamp() <= d.deadline { return Err(VaultError::NotExpired); }
+        if d.swept { return Err(VaultError::BadStatus); }
+
+        let rest = sub(d.total, d.claimed)?;
+        d.swept = true;
+        env.storage().persistent().set(&DataKey::MerkleDist(dist_id.clone()), &d);
+        if rest > 0 {
+            let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(d.fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+            token::Client::new(&env, &asset).transfer(&env.current_contract_address(), &to, &rest);
+        }
+        env.events().publish((symbol_short!("mrkl_swp"), dist_id, to), rest);
+        Ok(rest)
+    }
+
+    pub fn get_merkle_distribution(env: Env, dist_id: String) -> Result<MerkleDistribution, VaultError> {
+        env.storage().persistent().get(&DataKey::MerkleDist(dist_id)).ok_or(VaultError::NotFound)
+    }
+    pub fn is_merkle_claimed(env: Env, dist_id: String, investor: Address) -> bool {
+        env.storage().persistent().has(&DataKey::MerkleClaimed(dist_id, investor))
+    }
+
+    // -------- Distribuição paginada --------
+    // Para bases grandes de cotistas: start fixa valor e snapshot (total_supply), process paga
+    // lotes de até MAX_BATCH cotistas e finalize aloca o resíduo. O FundToken precisa ficar
+    // pausado durante toda a execução para que os saldos não mudem entre as transações.
+    // Encerrada a execução, clear_distribution apaga a lista de cotistas em lotes.
+
+    pub fn start_distribution(env: Env, receivable_id: String, holders: Vec<Address>) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if Self::needs_proposal(&env, &Self::receivable_fund(&env, &receivable_id)?, None) { return Err(VaultError::NeedsProposal); }
+        Self::start_run(env, receivable_id, holders)
+    }
+
+    fn start_run(env: Env, receivable_id: String, holders: Vec<Address>) -> Result<(), VaultError> {
+        let mut r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if let ReceivableKind::Installments = r.kind { return Err(VaultError::BadStatus); }
+        if let ReceivableStatus::Paid = r.status {} else { return Err(VaultError::BadStatus); }
+        // a lista de uma execução cancelada precisa ser apagada antes de recomeçar
+        if env.storage().persistent().has(&DataKey::DistClear(receivable_id.clone())) { return Err(VaultError::BadStatus); }
+        Self::ensure_single_class(&env, &r.fund_id)?;
+
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(r.fund_id.clone())).ok_or(VaultError::NotFound)?;
+        let token = FundTokenClient::new(&env, &fund.token);
+        if !token.is_paused() { return Err(VaultError::NotPaused); }
+        let total_shares = token.total_supply();
+        if total_shares <= 0 { return Err(VaultError::Invalid); }
+        let (buckets, takes, holdback, net) = Self::waterfall_split(&env, &r, r.paid_amount)?;
+        Self::apply_waterfall(&env, &r, &buckets, &takes, holdback)?;
+
+        let run = DistributionRun {
+            receivable_id: receivable_id.clone(),
+            fund_id: r.fund_id.clone(),
+            amount: net,
+            total_shares,
+            holder_count: 0,
+            cursor: 0,
+            distributed: 0,
+            balance_sum: 0,
+            candidates: vec![&env],
+            cutoff: None,
+            buckets,
+            takes,
+            holdback,
+        };
+        env.storage().persistent().set(&DataKey::DistRun(receivable_id.clone()), &run);
+        Self::append_holders(&env, &receivable_id, holders)?;
+
+        r.status = ReceivableStatus::Distributing;
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id.clone()), &r);
+        env.events().publish((symbol_short!("dist_init"), r.fund_id, receivable_id), r.paid_amount);
+        Ok(())
+    }
+
+    /// Acrescenta cotistas à lista da distribuição (listas maiores que MAX_BATCH vão em várias chamadas).
+    pub fn add_distribution_holders(env: Env, receivable_id: String, holders: Vec<Address>) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if let ReceivableStatus::Distributing = r.status {} else { return Err(VaultError::BadStatus); }
+        Self::append_holders(&env, &receivable_id, holders)
+    }
+
+    fn append_holders(env: &Env, receivable_id: &String, holders: Vec<Address>) -> Result<(), VaultError> {
+        if holders.len() > MAX_BATCH { return Err(VaultError::BatchTooLarge); }
+        let mut run: DistributionRun = env.storage().persistent().get(&DataKey::DistRun(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        for h in holders.iter() {
+            let seen = DataKey::DistSeen(receivable_id.clone(), h.clone());
+            if env.storage().persistent().has(&seen) { return Err(VaultError::DuplicateHolder); }
+            env.storage().persistent().set(&seen, &true);
+            env.storage().persistent().set(&DataKey::DistHolder(receivable_id.clone(), run.holder_count), &h);
+            run.holder_count += 1;
+        }
+        env.storage().persistent().set(&DataKey::DistRun(receivable_id.clone()), &run);
+        Ok(())
+    }
+
+    /// Paga os cotistas das posições [cursor, cursor + limit). `cursor` deve ser o cursor atual
+    /// da execução (protege contra reprocessar um lote). Retorna o novo cursor.
+    /// Numa passada de resíduo (ver `finalize_distribution`) não paga nada: só recolhe os
+    /// próximos candidatos ao resíduo.
+    pub fn process_distribution(env: Env, receivable_id: String, cursor: u32, limit: u32) -> Result<u32, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if limit == 0 { return Err(VaultError::Invalid); }
+        if limit > MAX_BATCH { return Err(VaultError::BatchTooLarge); }
+
+        let mut run: DistributionRun = env.storage().persistent().get(&DataKey::DistRun(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if cursor != run.cursor { return Err(VaultError::BadStatus); }
+        let token = Self::snapshot_token(&env, &run)?;
+        let policy = Self::holder_policy(env.clone(), run.fund_id.clone());
+
+        let end = core::cmp::min(cursor + limit, run.holder_count);
+        for pos in cursor..end {
+            let h: Address = env.storage().persistent().get(&DataKey::DistHolder(receivable_id.clone(), pos)).unwrap();
+            let bal = token.balance(&h);
+            if bal <= 0 { continue; }
+            let prod = bal.checked_mul(run.amount).ok_or(VaultError::Math)?;
+            let c = ResidueCandidate { holder: h.clone(), remainder: prod % run.total_shares, balance: bal, position: pos };
+            if let Some(cut) = &run.cutoff {
+                if ranks_before(cut.remainder, cut.balance, cut.position, c.remainder, c.balance, c.position) {
+                    Self::push_candidate(&mut run.candidates, c);
+                }
+                continue;
+            }
+            run.balance_sum = add(run.balance_sum, bal)?;
+            let share = prod / run.total_shares;
+            Self::push_candidate(&mut run.candidates, c);
+            if share > 0 {
+                run.distributed = add(run.distributed, share)?;
+                Self::pay_holder(&env, &run.fund_id, &token, &policy, h, share, symbol_short!("dist"))?;
+            }
+        }
+        run.cursor = end;
+        env.storage().persistent().set(&DataKey::DistRun(receivable_id), &run);
+        Ok(end)
+    }
+
+    /// Mantém os RESIDUE_CANDIDATES melhores candidatos ao resíduo, ordenados por `ranks_before`.
+    fn push_candidate(list: &mut Vec<ResidueCandidate>, c: ResidueCandidate) {
+        if c.remainder == 0 { return; }
+        let mut at = list.len();
+        for (i, o) in list.iter().enumerate() {
+            if ranks_before(c.remainder, c.balance, c.position, o.remainder, o.balance, o.position) { at = i as u32; break; }
+        }
+        if at >= RESIDUE_CANDIDATES { return; }
+        list.insert(at, c);
+        if list.len() > RESIDUE_CANDIDATES { list.pop_back(); }
+    }
+
+    /// Encerra a distribuição depois que todos os cotistas foram processados: aloca o resíduo
+    /// pelo maior resto, marca o recebível como distribuído e encerra a execução. Retorna true.
+    /// Cada chamada paga no máximo MAX_BATCH candidatos; se ainda houver candidatos guardados,
+    /// retorna false e basta finalizar de novo. Se o resíduo passar dos candidatos guardados,
+    /// abre uma passada de resíduo (cursor volta a 0, só entram restos abaixo do último pago) e
+    /// retorna false: processe de novo todos os cotistas e finalize outra vez.
+    pub fn finalize_distribution(env: Env, receivable_id: String) -> Result<bool, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let mut r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if let ReceivableStatus::Distributing = r.status {} else { return Err(VaultError::BadStatus); }
+        let mut run: DistributionRun = env.storage().persistent().get(&DataKey::DistRun(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if run.cursor < run.holder_count { return Err(VaultError::BadStatus); }
+        let token = Self::snapshot_token(&env, &run)?;
+        // faltou alguém: inclua com add_distribution_holders, processe e finalize de novo
+        if run.balance_sum != run.total_shares { return Err(VaultError::IncompleteHolders); }
+
+        // cada candidato recebe no máximo 1 stroop, na ordem de `ranks_before`
+        let residue = sub(run.amount, run.distributed)?;
+        let n = core::cmp::min(residue, core::cmp::min(run.candidates.len(), MAX_BATCH) as i128) as u32;
+        let policy = Self::holder_policy(env.clone(), run.fund_id.clone());
+        for c in run.candidates.slice(0..n).iter() {
+            Self::pay_holder(&env, &run.fund_id, &token, &policy, c.holder, 1, symbol_short!("dist_res"))?;
+        }
+        run.distributed = add(run.distributed, n as i128)?;
+        if n > 0 { run.cutoff = run.candidates.get(n - 1); }
+        run.candidates = run.candidates.slice(n..);
+
+        if run.distributed < run.amount {
+            if run.candidates.is_empty() {
+                // uma passada sem candidatos com resíduo em aberto: a conta não fecha
+                if n == 0 { return Err(VaultError::Math); }
+                run.cursor = 0;
+                env.events().publish((symbol_short!("dist_more"), run.fund_id.clone(), receivable_id.clone()), sub(run.amount, run.distributed)?);
+            }
+            env.storage().persistent().set(&DataKey::DistRun(receivable_id.clone()), &run);
+            return Ok(false);
+        }
+
+        Self::close_run(&env, &receivable_id, &run);
+        r.status = ReceivableStatus::Distributed;
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id.clone()), &r);
+        env.events().publish((symbol_short!("dist_sum"), run.fund_id, receivable_id), run.amount);
+        Ok(true)
+    }
+
+    /// Desfaz uma distribuição que ainda não pagou ninguém, inclusive a cascata; o recebível
+    /// volta para `Paid`.
+    pub fn cancel_distribution(env: Env, receivable_id: String) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let run: DistributionRun = env.storage().persistent().get(&DataKey::DistRun(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if run.cursor > 0 || run.distributed > 0 { return Err(VaultError::BadStatus); }
+        let mut r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        Self::reverse_waterfall(&env, &r, &run.buckets, &run.takes, run.holdback)?;
+        Self::close_run(&env, &receivable_id, &run);
+
+        r.status = ReceivableStatus::Paid;
+        env.storage().persistent().set(&DataKey::Receivable(receivable_id.clone()), &r);
+        env.events().publish((symbol_short!("dist_cncl"), run.fund_id, receivable_id), run.amount);
+        Ok(())
+    }
+
+    /// Apaga a execução; a lista de cotistas fica para `clear_distribution`.
+    fn close_run(env: &Env, receivable_id: &String, run: &DistributionRun) {
+        env.storage().persistent().remove(&DataKey::DistRun(receivable_id.clone()));
+        if run.holder_count > 0 {
+            env.storage().persistent().set(&DataKey::DistClear(receivable_id.clone()), &run.holder_count);
+        }
+    }
+
+    /// Apaga até `limit` cotistas (DistHolder/DistSeen) de uma execução encerrada ou cancelada,
+    /// do fim da lista para o começo. Retorna quantos ainda faltam.
+    pub fn clear_distribution(env: Env, receivable_id: String, limit: u32) -> Result<u32, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if limit == 0 { return Err(VaultError::Invalid); }
+        if limit > MAX_BATCH { return Err(VaultError::BatchTooLarge); }
+
+        let clear = DataKey::DistClear(receivable_id.clone());
+        let left: u32 = env.storage().persistent().get(&clear).ok_or(VaultError::NotFound)?;
+        let end = left.saturating_sub(limit);
+        for pos in end..left {
+            let key = DataKey::DistHolder(receivable_id.clone(), pos);
+            let h: Address = env.storage().persistent().get(&key).unwrap();
+            env.storage().persistent().remove(&DataKey::DistSeen(receivable_id.clone(), h));
+            env.storage().persistent().remove(&key);
+        }
+        if end == 0 {
+            env.storage().persistent().remove(&clear);
+        } else {
+            env.storage().persistent().set(&clear, &end);
+        }
+        Ok(end)
+    }
+
+    /// Confere que o snapshot continua válido: token pausado e supply igual ao do início.
+    fn snapshot_token<'a>(env: &'a Env, run: &DistributionRun) -> Result<FundTokenClient<'a>, VaultError> {
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(run.fund_id.clone())).ok_or(VaultError::NotFound)?;
+        let token = FundTokenClient::new(env, &fund.token);
+        if !token.is_paused() { return Err(VaultError::NotPaused); }
+        if token.total_supply() != run.total_shares { return Err(VaultError::SnapshotChanged); }
+        Ok(token)
+    }
+
+    pub fn get_distribution(env: Env, receivable_id: String) -> Result<DistributionRun, VaultError> {
+        env.storage().persistent().get(&DataKey::DistRun(receivable_id)).ok_or(VaultError::NotFound)
+    }
+
+    // -------- Parcelas --------
+    pub fn mark_installment_paid(env: Env, id: String, index: u32, amount: i128) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if amount <= 0 { return Err(VaultError::Invalid); }
+
+        let mut r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id.clone())).ok_or(VaultError::NotFound)?;
+        if let ReceivableStatus::Pending = r.status {} else { return Err(VaultError::BadStatus); }
+        let mut installments: Vec<Installment> = env.storage().persistent().get(&DataKey::Installments(id.clone())).ok_or(VaultError::NotFound)?;
+        let mut inst = installments.get(index).ok_or(VaultError::NotFound)?;
+        if let ReceivableStatus::Pending = inst.status {} else { return Err(VaultError::BadStatus); }
+        if amount > sub(inst.amount, inst.paid_amount)? { return Err(VaultError::Invalid); }
+
+        // pagamento parcial fica registrado e a parcela segue em aberto pelo saldo
+        let now = env.ledger().timestamp();
+        inst.paid_amount = add(inst.paid_amount, amount)?;
+        inst.paid_date = now;
+        if inst.paid_amount == inst.amount { inst.status = ReceivableStatus::Paid; }
+        installments.set(index, inst);
+
+        r.paid_amount = add(r.paid_amount, amount)?;
+        if installments.iter().all(|i| !matches!(i.status, ReceivableStatus::Pending)) {
+            r.status = ReceivableStatus::Paid;
+            r.paid_date = now;
+        }
+        env.storage().persistent().set(&DataKey::Installments(id.clone()), &installments);
+        env.storage().persistent().set(&DataKey::Receivable(id.clone()), &r);
+
+        let tot: i128 = env.storage().instance().get(&DataKey::TotalPaid).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TotalPaid, &add(tot, amount)?);
+        Self::accrue_commission(&env, &r, amount)?;
+        env.events().publish((symbol_short!("inst_paid"), r.fund_id, id, index), amount);
+        Ok(())
+    }
+
+    pub fn distribute_installment(env: Env, id: String, index: u32, holders: Vec<Address>) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if Self::needs_proposal(&env, &Self::receivable_fund(&env, &id)?, None) { return Err(VaultError::NeedsProposal); }
+        Self::distribute_paid_installment(env, id, index, holders)
+    }
+
+    fn distribute_paid_installment(env: Env, id: String, index: u32, holders: Vec<Address>) -> Result<(), VaultError> {
+        let mut r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id.clone())).ok_or(VaultError::NotFound)?;
+        let mut installments: Vec<Installment> = env.storage().persistent().get(&DataKey::Installments(id.clone())).ok_or(VaultError::NotFound)?;
+        let mut inst = installments.get(index).ok_or(VaultError::NotFound)?;
+        if let ReceivableStatus::Paid = inst.status {} else { return Err(VaultError::BadStatus); }
+
+        let paid = inst.paid_amount;
+        Self::distribute_amount(&env, &r, &holders, paid)?;
+
+        inst.status = ReceivableStatus::Distributed;
+        installments.set(index, inst);
+        if installments.iter().all(|i| matches!(i.status, ReceivableStatus::Distributed)) {
+            r.status = ReceivableStatus::Distributed;
+            env.storage().persistent().set(&DataKey::Receivable(id.clone()), &r);
+        }
+        env.storage().persistent().set(&DataKey::Installments(id.clone()), &installments);
+        env.events().publish((symbol_short!("dist_sum"), r.fund_id, id, index), paid);
+        Ok(())
+    }
+
+    /// Parcelas do recebível. Recebível simples é tratado como parcela única.
+    pub fn installments(env: Env, id: String) -> Result<Vec<Installment>, VaultError> {
+        let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id.clone())).ok_or(VaultError::NotFound)?;
+        match r.kind {
+            ReceivableKind::Installments => env.storage().persistent().get(&DataKey::Installments(id)).ok_or(VaultError::NotFound),
+            ReceivableKind::Single => Ok(vec![&env, Installment {
+                due_date: r.due_date,
+                amount: r.face_value,
+                status: r.status,
+                paid_amount: r.paid_amount,
+                paid_date: r.paid_date,
+            }]),
+        }
+    }
+
+    /// Próxima parcela em aberto (None se tudo já foi pago).
+    pub fn next_due(env: Env, id: String) -> Result<Option<Installment>, VaultError> {
+        let all = Self::installments(env, id)?;
+        Ok(all.iter().find(|i| matches!(i.status, ReceivableStatus::Pending)))
+    }
+
+    /// Valor de face ainda não pago.
+    pub fn outstanding(env: Env, id: String) -> Result<i128, VaultError> {
+        let mut total: i128 = 0;
+        for i in Self::installments(env, id)?.iter() {
+            if let ReceivableStatus::Pending = i.status { total = add(total, sub(i.amount, i.paid_amount)?)?; }
+        }
+        Ok(total)
+    }
+
+    /// Índices das parcelas vencidas e não pagas no timestamp atual do ledger.
+    pub fn overdue_installments(env: Env, id: String) -> Result<Vec<u32>, VaultError> {
+        let now = env.ledger().timestamp();
+        let mut out: Vec<u32> = vec![&env];
+        for (idx, i) in Self::installments(env.clone(), id)?.iter().enumerate() {
+            if matches!(i.status, ReceivableStatus::Pending) && i.due_date < now { out.push_back(idx as u32); }
+        }
+        Ok(out)
+    }
+
+    // -------- Provisão para devedores duvidosos (PDD) --------
+    pub fn set_provision_table(env: Env, fund_id: String, table: ProvisionTable) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        if table.rates.len() != table.bounds.len() + 1 || table.rates.len() > MAX_BUCKETS { return Err(VaultError::Invalid); }
+        let mut prev: Option<u32> = None;
+        for b in table.bounds.iter() {
+            if let Some(p) = prev { if b <= p { return Err(VaultError::Invalid); } }
+            prev = Some(b);
+        }
+        if table.rates.iter().any(|r| r as i128 > BPS) { return Err(VaultError::Invalid); }
+        env.storage().persistent().set(&DataKey::ProvisionTable(fund_id.clone()), &table);
+        env.events().publish((symbol_short!("pdd_set"), fund_id), table.rates.len());
+        Ok(())
+    }
+
+    /// Tabela do fundo; sem configuração, as faixas da Res. CMN 2.682 (A a H).
+    pub fn provision_table(env: Env, fund_id: String) -> ProvisionTable {
+        env.storage().persistent().get(&DataKey::ProvisionTable(fund_id)).unwrap_or(ProvisionTable {
+            bounds: vec![&env, 15, 30, 60, 90, 120, 150, 180],
+            rates: vec![&env, 50, 100, 300, 1_000, 3_000, 5_000, 7_000, 10_000],
+        })
+    }
+
+    pub fn aging(env: Env, receivable_id: String) -> Result<Aging, VaultError> {
+        let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id)).ok_or(VaultError::NotFound)?;
+        let table = Self::provision_table(env.clone(), r.fund_id.clone());
+        Self::age(&env, &r, &table, env.ledger().timestamp())
+    }
+
+    fn age(env: &Env, r: &Receivable, table: &ProvisionTable, now: u64) -> Result<Aging, VaultError> {
+        // só títulos em aberto têm exposição; baixados já são perda realizada
+        let (oldest_due, exposure) = match r.status {
+            ReceivableStatus::Pending => match r.kind {
+                ReceivableKind::Single => (Some(r.due_date), sub(r.face_value, r.paid_amount)?),
+                ReceivableKind::Installments => {
+                    let all: Vec<Installment> = env.storage().persistent().get(&DataKey::Installments(r.id.clone())).ok_or(VaultError::NotFound)?;
+                    let mut oldest: Option<u64> = None;
+                    let mut open: i128 = 0;
+                    for i in all.iter() {
+                        if let ReceivableStatus::Pending = i.status {
+                            if oldest.is_none() { oldest = Some(i.due_date); }
+                            open = add(open, sub(i.amount, i.paid_amount)?)?;
+                        }
+                    }
+                    (oldest, open)
+                }
+            },
+            _ => (None, 0),
+        };
+        let days_overdue = match oldest_due {
+            Some(due) if now > due => ((now - due) / DAY).min(u32::MAX as u64) as u32,
+            _ => 0,
+        };
+        let bucket = table.bounds.iter().position(|b| days_overdue <= b).unwrap_or(table.bounds.len() as usize) as u32;
+        let rate = table.rates.get(bucket).unwrap_or(0) as i128;
+        let provision = exposure.checked_mul(rate).ok_or(VaultError::Math)? / BPS;
+        Ok(Aging { days_overdue, bucket, exposure, provision })
+    }
+
+    /// PDD do fundo no timestamp atual: cada recebível é provisionado pela faixa da sua parcela
+    /// em aberto mais antiga, e as provisões individuais são somadas por faixa.
+    pub fn provision(env: Env, fund_id: String) -> Result<ProvisionReport, VaultError> {
+        let count: u32 = env.storage().persistent().get(&DataKey::FundReceivableCount(fund_id.clone())).unwrap_or(0);
+        Self::provision_page(env, fund_id, 0, count)
+    }
+
+    /// Mesmo cálculo de `provision`, restrito às posições `[start, start + limit)` do fundo,
+    /// para fundos grandes demais para uma única chamada.
+    pub fn provision_page(env: Env, fund_id: String, start: u32, limit: u32) -> Result<ProvisionReport, VaultError> {
+        let table = Self::provision_table(env.clone(), fund_id.clone());
+        let buckets = table.rates.len();
+        let mut report = ProvisionReport { exposure: vec![&env], provision: vec![&env], total: 0 };
+        for _ in 0..buckets { report.exposure.push_back(0); report.provision.push_back(0); }
+
+        let count: u32 = env.storage().persistent().get(&DataKey::FundReceivableCount(fund_id.clone())).unwrap_or(0);
+        let end = start.saturating_add(limit).min(count);
+        for pos in start..end {
+            let id: String = env.storage().persistent().get(&DataKey::FundReceivable(fund_id.clone(), pos)).unwrap();
+            let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id)).unwrap();
+            let a = Self::age(&env, &r, &table, env.ledger().timestamp())?;
+            if a.exposure == 0 { continue; }
+            report.exposure.set(a.bucket, add(report.exposure.get(a.bucket).unwrap(), a.exposure)?);
+            report.provision.set(a.bucket, add(report.provision.get(a.bucket).unwrap(), a.provision)?);
+            report.total = add(report.total, a.provision)?;
+        }
+        Ok(report)
+    }
+
+    /// Ids dos recebíveis do fundo, em ordem de registro, para recomputar a PDD fora da rede.
+    pub fn fund_receivables(env: Env, fund_id: String, start: u32, limit: u32) -> Vec<String> {
+        let count: u32 = env.storage().persistent().get(&DataKey::FundReceivableCount(fund_id.clone())).unwrap_or(0);
+        let mut out: Vec<String> = vec![&env];
+        for pos in start..start.saturating_add(limit).min(count) {
+            out.push_back(env.storage().persistent().get(&DataKey::FundReceivable(fund_id.clone(), pos)).unwrap());
+        }
+        out
+    }
+
+    // -------- Valor da cota (NAV) --------
+    /// Registra a cessão: preço pago e taxa diária da curva (calculada fora da rede a partir
+    /// do preço, do valor de face e do prazo). O preço sai do caixa do fundo. Comissões acumuladas
+    /// não reservam caixa: são provisionadas pela cascata e pagas com `fund_commission`.
+    pub fn set_acquisition(env: Env, receivable_id: String, price: i128, daily_rate: i128) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(receivable_id.clone())).ok_or(VaultError::NotFound)?;
+        if let ReceivableStatus::Pending = r.status {} else { return Err(VaultError::BadStatus); }
+        if price <= 0 || price > r.face_value || daily_rate < 0 || daily_rate >= RATE_SCALE { return Err(VaultError::Invalid); }
+        if env.storage().persistent().has(&DataKey::Acquisition(receivable_id.clone())) { return Err(VaultError::DuplicateId); }
+        let cash: i128 = env.storage().persistent().get(&DataKey::Cash(r.fund_id.clone())).unwrap_or(0);
+        if cash < price { return Err(VaultError::InsufficientCash); }
+        Self::bump(&env, DataKey::Cash(r.fund_id.clone()), -price)?;
+        let acq = Acquisition { price, daily_rate, acquired_at: env.ledger().timestamp() };
+        env.storage().persistent().set(&DataKey::Acquisition(receivable_id.clone()), &acq);
+        env.events().publish((symbol_short!("acquire"), r.fund_id, receivable_id), price);
+        Ok(())
+    }
+
+    pub fn acquisition(env: Env, receivable_id: String) -> Option<Acquisition> {
+        env.storage().persistent().get(&DataKey::Acquisition(receivable_id))
+    }
+
+    /// Valor presente dos títulos em aberto (sem cessão registrada: valor de face), recebimentos
+    /// ainda não distribuídos, caixa e reserva, menos comissões a provisionar e PDD. Percorre todos
+    /// os títulos do fundo: é consulta; subscrição e resgate usam o NAV marcado por `record_nav`.
+    pub fn nav(env: Env, fund_id: String) -> Result<Nav, VaultError> {
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        let count: u32 = env.storage().persistent().get(&DataKey::FundReceivableCount(fund_id.clone())).unwrap_or(0);
+        let mut n = zero_nav();
+        Self::value_page(&env, &fund_id, 0, count, env.ledger().timestamp(), &mut n)?;
+        Self::close_nav(&env, &fund_id, n)
+    }
+
+    /// Soma em `n` os títulos das posições `[start, end)` do fundo, avaliados em `now`.
+    fn value_page(env: &Env, fund_id: &String, start: u32, end: u32, now: u64, n: &mut Nav) -> Result<(), VaultError> {
+        let table = Self::provision_table(env.clone(), fund_id.clone());
+        for pos in start..end {
+            let id: String = env.storage().persistent().get(&DataKey::FundReceivable(fund_id.clone(), pos)).unwrap();
+            let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id.clone())).unwrap();
+            let rate = Self::acquisition(env.clone(), id.clone()).map_or(0, |a| a.daily_rate);
+            let pv = |face: i128, due: u64| discount(face, rate, if due > now { (due - now) / DAY } else { 0 });
+            // parcelas em aberto só contam com o título em aberto (baixado, nada a receber)
+            let open = matches!(r.status, ReceivableStatus::Pending);
+            match r.kind {
+                // pago a menor: o saldo segue a receber e o já pago conta como arrecadado
+                ReceivableKind::Single => match r.status {
+                    ReceivableStatus::Pending => {
+                        n.receivables = add(n.receivables, pv(sub(r.face_value, r.paid_amount)?, r.due_date)?)?;
+                        n.collections = add(n.collections, r.paid_amount)?;
+                    }
+                    ReceivableStatus::Paid => n.collections = add(n.collections, r.paid_amount)?,
+                    _ => {}
+                },
+                ReceivableKind::Installments => {
+                    let all: Vec<Installment> = env.storage().persistent().get(&DataKey::Installments(id)).unwrap();
+                    for i in all.iter() {
+                        match i.status {
+                            ReceivableStatus::Pending if open => {
+                                n.receivables = add(n.receivables, pv(sub(i.amount, i.paid_amount)?, i.due_date)?)?;
+                                n.collections = add(n.collections, i.paid_amount)?;
+                            }
+                            ReceivableStatus::Paid => n.collections = add(n.collections, i.paid_amount)?,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            n.provision = add(n.provision, Self::age(env, &r, &table, now)?.provision)?;
+        }
+        Ok(())
+    }
+
+    /// Completa `n` com caixa, reserva e comissões a provisionar de agora e calcula o NAV.
+    fn close_nav(env: &Env, fund_id: &String, mut n: Nav) -> Result<Nav, VaultError> {
+        n.cash = env.storage().persistent().get(&DataKey::Cash(fund_id.clone())).unwrap_or(0);
+        n.reserve = Self::reserve_balance(env.clone(), fund_id.clone());
+        n.fees = env.storage().persistent().get(&DataKey::CommissionUnfunded(fund_id.clone())).unwrap_or(0);
+        n.nav = sub(add(add(add(n.receivables, n.collections)?, n.cash)?, n.reserve)?, add(n.fees, n.provision)?)?;
+        Ok(n)
+    }
+
+    /// NAV por cota inteira (escala PRICE_SCALE), sobre o supply atual do FundToken do fundo.
+    pub fn quota_price(env: Env, fund_id: String) -> Result<i128, VaultError> {
+        let nav = Self::nav(env.clone(), fund_id.clone())?.nav;
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id)).ok_or(VaultError::NotFound)?;
+        let supply = FundTokenClient::new(&env, &fund.token).total_supply();
+        if supply <= 0 { return Err(VaultError::NoSupply); }
+        Ok(nav.checked_mul(PRICE_SCALE).ok_or(VaultError::Math)? / supply)
+    }
+
+    /// Marca o NAV em páginas de até `limit` títulos (MAX_NAV_PAGE), todos avaliados no instante
+    /// da primeira página. Retorna `None` enquanto faltam títulos; na última página grava a entrada
+    /// no histórico datado do fundo e a retorna. Uma marcação com mais de um dia recomeça.
+    pub fn record_nav(env: Env, fund_id: String, limit: u32) -> Result<Option<NavEntry>, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if limit == 0 { return Err(VaultError::Invalid); }
+        if limit > MAX_NAV_PAGE { return Err(VaultError::BatchTooLarge); }
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+
+        let now = env.ledger().timestamp();
+        let key = DataKey::NavRun(fund_id.clone());
+        let mut run: NavRun = match env.storage().persistent().get::<_, NavRun>(&key) {
+            Some(run) if now < run.at.saturating_add(NAV_MAX_AGE) => run,
+            _ => NavRun { at: now, cursor: 0, value: zero_nav() },
+        };
+        let count: u32 = env.storage().persistent().get(&DataKey::FundReceivableCount(fund_id.clone())).unwrap_or(0);
+        let end = run.cursor.saturating_add(limit).min(count);
+        Self::value_page(&env, &fund_id, run.cursor, end, run.at, &mut run.value)?;
+        run.cursor = end;
+        if end < count {
+            env.storage().persistent().set(&key, &run);
+            return Ok(None);
+        }
+        env.storage().persistent().remove(&key);
+        let n = Self::close_nav(&env, &fund_id, run.value)?;
+        Self::write_nav(&env, &fund_id, n.nav, run.at).map(Some)
+    }
+
+    pub fn nav_run(env: Env, fund_id: String) -> Option<NavRun> {
+        env.storage().persistent().get(&DataKey::NavRun(fund_id))
+    }
+
+    fn write_nav(env: &Env, fund_id: &String, nav: i128, at: u64) -> Result<NavEntry, VaultError> {
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        let supply = FundTokenClient::new(env, &fund.token).total_supply();
+        let price = if supply > 0 { nav.checked_mul(PRICE_SCALE).ok_or(VaultError::Math)? / supply } else { 0 };
+        let entry = NavEntry { nav, supply, price, at };
+
+        let pos: u32 = env.storage().persistent().get(&DataKey::NavCount(fund_id.clone())).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::NavHistory(fund_id.clone(), pos), &entry);
+        env.storage().persistent().set(&DataKey::NavCount(fund_id.clone()), &(pos + 1));
+        env.events().publish((symbol_short!("nav"), fund_id.clone(), pos), (nav, price));
+        Ok(entry)
+    }
+
+    /// Último NAV marcado, desde que tenha menos de NAV_MAX_AGE.
+    fn fresh_nav(env: &Env, fund_id: &String) -> Result<NavEntry, VaultError> {
+        match Self::latest_nav(env.clone(), fund_id.clone()) {
+            Some(e) if env.ledger().timestamp() < e.at.saturating_add(NAV_MAX_AGE) => Ok(e),
+            _ => Err(VaultError::StaleNav),
+        }
+    }
+
+    pub fn nav_history(env: Env, fund_id: String, start: u32, limit: u32) -> Vec<NavEntry> {
+        let count: u32 = env.storage().persistent().get(&DataKey::NavCount(fund_id.clone())).unwrap_or(0);
+        let mut out: Vec<NavEntry> = vec![&env];
+        for pos in start..start.saturating_add(limit).min(count) {
+            out.push_back(env.storage().persistent().get(&DataKey::NavHistory(fund_id.clone(), pos)).unwrap());
+        }
+        out
+    }
+
+    pub fn latest_nav(env: Env, fund_id: String) -> Option<NavEntry> {
+        let count: u32 = env.storage().persistent().get(&DataKey::NavCount(fund_id.clone())).unwrap_or(0);
+        if count == 0 { return None; }
+        env.storage().persistent().get(&DataKey::NavHistory(fund_id, count - 1))
+    }
+
+    pub fn cash(env: Env, fund_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::Cash(fund_id)).unwrap_or(0)
+    }
+
+    // -------- Subscrição --------
+    pub fn set_subscription_terms(env: Env, fund_id: String, terms: SubscriptionTerms) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        if terms.min_amount < 0 || terms.initial_price <= 0 { return Err(VaultError::Invalid); }
+        env.storage().persistent().set(&DataKey::SubscriptionTerms(fund_id.clone()), &terms);
+        env.events().publish((symbol_short!("sub_terms"), fund_id), (terms.min_amount, terms.initial_price));
+        Ok(())
+    }
+
+    pub fn subscription_terms(env: Env, fund_id: String) -> Option<SubscriptionTerms> {
+        env.storage().persistent().get(&DataKey::SubscriptionTerms(fund_id))
+    }
+
+    /// Aporte de `amount` no ativo de pagamento; emite cotas ao preço do último NAV marcado
+    /// (arredondado para baixo, a favor dos cotistas existentes) e retorna a quantidade emitida. O vault precisa
+    /// ser minter do FundToken. Fundos com classes de cotas não aceitam subscrição por aqui.
+    pub fn subscribe(env: Env, investor: Address, fund_id: String, amount: i128) -> Result<i128, VaultError> {
+        investor.require_auth();
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        match fund.status {
+            Status::Approved => {}
+            Status::Suspended => return Err(VaultError::FundSuspended),
+            _ => return Err(VaultError::FundNotApproved),
+        }
+        Self::ensure_single_class(&env, &fund_id)?;
+        let terms: SubscriptionTerms = env.storage().persistent().get(&DataKey::SubscriptionTerms(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+        if amount <= 0 { return Err(VaultError::Invalid); }
+        if amount < terms.min_amount { return Err(VaultError::BelowMinimum); }
+
+        let fund_token = FundTokenClient::new(&env, &fund.token);
+        if !fund_token.is_whitelisted(&investor) { return Err(VaultError::NotWhitelisted); }
+        let supply = fund_token.total_supply();
+        let price = if supply == 0 { terms.initial_price } else { Self::fresh_nav(&env, &fund_id)?.price };
+        if price <= 0 { return Err(VaultError::Invalid); }
+        let quotas = amount.checked_mul(PRICE_SCALE).ok_or(VaultError::Math)? / price;
+        if quotas == 0 { return Err(VaultError::BelowMinimum); }
+        if add(supply, quotas)? > fund_token.max_supply() { return Err(VaultError::MaxSupplyExceeded); }
+
+        token::Client::new(&env, &asset).transfer(&investor, &env.current_contract_address(), &amount);
+        Self::bump(&env, DataKey::Cash(fund_id.clone()), amount)?;
+        fund_token.mint_by(&env.current_contract_address(), &investor, &quotas);
+        env.events().publish((symbol_short!("subscribe"), fund_id, investor), (amount, quotas, price));
+        Ok(quotas)
+    }
+
+    // -------- Resgate --------
+    pub fn set_redemption_terms(env: Env, fund_id: String, terms: RedemptionTerms) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+        if terms.gate_bps == 0 || terms.gate_bps as i128 > BPS || terms.period == 0 || terms.min_quotas < 0 { return Err(VaultError::Invalid); }
+        env.storage().persistent().set(&DataKey::RedemptionTerms(fund_id.clone()), &terms);
+        env.events().publish((symbol_short!("rdm_terms"), fund_id), (terms.notice, terms.gate_bps, terms.period));
+        Ok(())
+    }
+
+    pub fn redemption_terms(env: Env, fund_id: String) -> Option<RedemptionTerms> {
+        env.storage().persistent().get(&DataKey::RedemptionTerms(fund_id))
+    }
+
+    /// Pede o resgate de `quotas` (ao menos `min_quotas` dos termos): as cotas vão para o vault (que
+    /// precisa estar na whitelist do FundToken) e ficam travadas até a liquidação, após o prazo de
+    /// cotização. O rendimento que elas recebem no vault é repassado ao cotista em `settle_redemptions`.
+    pub fn request_redemption(env: Env, investor: Address, fund_id: String, quotas: i128) -> Result<u32, VaultError> {
+        investor.require_auth();
+        if quotas <= 0 { return Err(VaultError::Invalid); }
+        let terms: RedemptionTerms = env.storage().persistent().get(&DataKey::RedemptionTerms(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        if quotas < terms.min_quotas { return Err(VaultError::BelowMinimum); }
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        Self::ensure_single_class(&env, &fund_id)?;
+        let mut queue = Self::redemption_queue(env.clone(), fund_id.clone());
+        if queue.len() >= MAX_REDEMPTIONS { return Err(VaultError::QueueFull); }
+
+        let fund_token = FundTokenClient::new(&env, &fund.token);
+        fund_token.transfer(&investor, &env.current_contract_address(), &quotas);
+
+        let id: u32 = env.storage().instance().get(&DataKey::RedemptionCount).unwrap_or(0);
+        env.storage().instance().set(&DataKey::RedemptionCount, &(id + 1));
+        let now = env.ledger().timestamp();
+        let r = Redemption {
+            id,
+            fund_id: fund_id.clone(),
+            investor: investor.clone(),
+            quotas,
+            requested_at: now,
+            eligible_at: now.saturating_add(terms.notice),
+            reward_mark: fund_token.reward_per_share(),
+        };
+        env.storage().persistent().set(&DataKey::Redemption(id), &r);
+        queue.push_back(id);
+        env.storage().persistent().set(&DataKey::RedemptionQueue(fund_id.clone()), &queue);
+        env.events().publish((symbol_short!("rdm_req"), fund_id, investor), (id, quotas, r.eligible_at));
+        Ok(id)
+    }
+
+    /// Liquida os pedidos que já cumpriram o prazo de cotização, ao último NAV marcado
+    /// (`record_nav`, com menos de um dia), em páginas de até `limit` pedidos (MAX_SETTLE_PAGE).
+    /// O total pago fica limitado ao caixa do fundo e ao que resta do gate do período; se a
+    /// demanda passar disso, cada pedido recebe a mesma fração e o saldo segue na fila. Todo
+    /// pedido, elegível ou não, recebe o rendimento que as suas cotas travadas acumularam no vault.
+    /// Retorna `None` enquanto faltam pedidos; na última página, o valor pago pelas cotas.
+    pub fn settle_redemptions(env: Env, fund_id: String, limit: u32) -> Result<Option<i128>, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if limit == 0 { return Err(VaultError::Invalid); }
+        if limit > MAX_SETTLE_PAGE { return Err(VaultError::BatchTooLarge); }
+        let terms: RedemptionTerms = env.storage().persistent().get(&DataKey::RedemptionTerms(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        let fund: FundRecord = env.storage().persistent().get(&DataKey::Fund(fund_id.clone())).ok_or(VaultError::NotFound)?;
+        let asset: Address = env.storage().persistent().get(&DataKey::PaymentAsset(fund_id.clone())).ok_or(VaultError::NoPaymentAsset)?;
+        let fund_token = FundTokenClient::new(&env, &fund.token);
+        let key = DataKey::SettleRun(fund_id.clone());
+        let mut run: SettleRun = match env.storage().persistent().get::<_, SettleRun>(&key) {
+            Some(run) => run,
+            None => Self::open_settlement(&env, &fund_id, &terms, &fund_token)?,
+        };
+        let mut queue = Self::redemption_queue(env.clone(), fund_id.clone());
+        let mut budget = limit;
+
+        // 1ª passada: demanda elegível, ao preço da abertura
+        if !run.paying {
+            let end = run.cursor.saturating_add(budget).min(run.count);
+            for pos in run.cursor..end {
+                let r: Redemption = env.storage().persistent().get(&DataKey::Redemption(queue.get(pos).unwrap())).unwrap();
+                if r.eligible_at <= run.at { run.demand = add(run.demand, r.quotas.checked_mul(run.price).ok_or(VaultError::Math)? / PRICE_SCALE)?; }
+            }
+            budget -= end - run.cursor;
+            run.cursor = end;
+            if end == run.count {
+                // separa do caixa o que a 2ª passada pode pagar
+                let cash = Self::cash(env.clone(), fund_id.clone());
+                let capacity = core::cmp::min(sub(run.gate.limit, run.gate.used)?, cash).max(0);
+                if run.demand > 0 && capacity > 0 && run.price > 0 {
+                    (run.num, run.den) = if run.demand <= capacity { (1, 1) } else { (capacity, run.demand) };
+                    run.reserved = core::cmp::min(run.demand, capacity);
+                    Self::bump(&env, DataKey::Cash(fund_id.clone()), -run.reserved)?;
+                }
+                run.paying = true;
+                run.cursor = 0;
+            }
+        }
+
+        // 2ª passada: repassa o rendimento das cotas travadas, paga a fração de cada pedido e
+        // mantém o saldo na fila
+        let filling = run.reserved > 0;
+        let payment = token::Client::new(&env, &asset);
+        let mut removed = false;
+        let mut burned: i128 = 0;
+        let mut rewards: i128 = 0;
+        while run.paying && budget > 0 && run.cursor < run.count {
+            budget -= 1;
+            let id = queue.get(run.cursor).unwrap();
+            let mut r: Redemption = env.storage().persistent().get(&DataKey::Redemption(id)).unwrap();
+            let mut changed = r.reward_mark != run.rps;
+            let reward = r.quotas.checked_mul(sub(run.rps, r.reward_mark)?).ok_or(VaultError::Math)? / REWARD_PRECISION;
+            r.reward_mark = run.rps;
+            if reward > 0 {
+                payment.transfer(&env.current_contract_address(), &r.investor, &reward);
+                rewards = add(rewards, reward)?;
+                env.events().publish((symbol_short!("rdm_rwd"), fund_id.clone(), r.investor.clone()), (id, reward));
+            }
+            if filling && r.eligible_at <= run.at {
+                let fill = r.quotas.checked_mul(run.num).ok_or(VaultError::Math)? / run.den;
+                let pay = fill.checked_mul(run.price).ok_or(VaultError::Math)? / PRICE_SCALE;
+                // cotas que renderiam 0 não são queimadas
+                if pay > 0 {
+                    payment.transfer(&env.current_contract_address(), &r.investor, &pay);
+                    r.quotas = sub(r.quotas, fill)?;
+                    burned = add(burned, fill)?;
+                    run.paid = add(run.paid, pay)?;
+                    changed = true;
+                    env.events().publish((symbol_short!("rdm_pay"), fund_id.clone(), r.investor.clone()), (id, fill, pay));
+                }
+                if r.quotas > 0 { env.events().publish((symbol_short!("rdm_carry"), fund_id.clone(), r.investor.clone()), (id, r.quotas)); }
+            }
+            if r.quotas > 0 {
+                if changed { env.storage().persistent().set(&DataKey::Redemption(id), &r); }
+                run.cursor += 1;
+            } else {
+                env.storage().persistent().remove(&DataKey::Redemption(id));
+                queue.remove(run.cursor);
+                run.count -= 1;
+                removed = true;
+            }
+        }
+        if rewards > 0 {
+            Self::bump(&env, DataKey::Claimable(fund_id.clone()), -rewards)?;
+            run.rewards = add(run.rewards, rewards)?;
+        }
+        if burned > 0 { fund_token.burn(&env.current_contract_address(), &burned); }
+        if removed { env.storage().persistent().set(&DataKey::RedemptionQueue(fund_id.clone()), &queue); }
+        if !run.paying || run.cursor < run.count {
+            env.storage().persistent().set(&key, &run);
+            return Ok(None);
+        }
+
+        env.storage().persistent().remove(&key);
+        // o vault recebeu o rendimento das cotas travadas; a sobra de arredondamento volta ao
+        // acumulador para os demais cotistas
+        let extra = sub(run.taken, run.rewards)?;
+        if extra > 0 { Self::launch_reward(&env, &fund.token, extra); }
+        if !filling { return Ok(Some(0)); }
+        Self::bump(&env, DataKey::Cash(fund_id.clone()), sub(run.reserved, run.paid)?)?;
+        run.gate.used = add(run.gate.used, run.paid)?;
+        env.storage().persistent().set(&DataKey::Gate(fund_id), &run.gate);
+        Ok(Some(run.paid))
+    }
+
+    /// Abre a liquidação: NAV marcado, gate do período e rendimento das cotas travadas até agora.
+    fn open_settlement(env: &Env, fund_id: &String, terms: &RedemptionTerms, fund_token: &FundTokenClient) -> Result<SettleRun, VaultError> {
+        let now = env.ledger().timestamp();
+        let entry = Self::fresh_nav(env, fund_id)?;
+        let gate = match env.storage().persistent().get::<_, GateWindow>(&DataKey::Gate(fund_id.clone())) {
+            Some(g) if now < g.start.saturating_add(terms.period) => g,
+            _ => GateWindow { start: now, limit: entry.nav.max(0).checked_mul(terms.gate_bps as i128).ok_or(VaultError::Math)? / BPS, used: 0 },
+        };
+        let count = Self::redemption_queue(env.clone(), fund_id.clone()).len();
+        let taken = if count > 0 { fund_token.take_reward(&env.current_contract_address()) } else { 0 };
+        Ok(SettleRun {
+            at: now,
+            price: entry.price,
+            rps: fund_token.reward_per_share(),
+            taken,
+            count,
+            cursor: 0,
+            paying: false,
+            demand: 0,
+            gate,
+            num: 0,
+            den: 1,
+            reserved: 0,
+            paid: 0,
+            rewards: 0,
+        })
+    }
+
+    pub fn redemption(env: Env, id: u32) -> Option<Redemption> {
+        env.storage().persistent().get(&DataKey::Redemption(id))
+    }
+    pub fn redemption_queue(env: Env, fund_id: String) -> Vec<u32> {
+        env.storage().persistent().get(&DataKey::RedemptionQueue(fund_id)).unwrap_or(vec![&env])
+    }
+    pub fn gate_window(env: Env, fund_id: String) -> Option<GateWindow> {
+        env.storage().persistent().get(&DataKey::Gate(fund_id))
+    }
+    pub fn settle_run(env: Env, fund_id: String) -> Option<SettleRun> {
+        env.storage().persistent().get(&DataKey::SettleRun(fund_id))
+    }
+
+    // -------- Documentos --------
+    /// Ancora o hash de um documento (NF, cessão, entrega, KYC) num recebível ou entidade.
+    /// Recebíveis: só o gestor. Cedente/Sacado: o gestor ou o consultor que propôs o cadastro,
+    /// enquanto designado para o fundo dele.
+    pub fn attach_document(
+        env: Env,
+        caller: Address,
+        target: DocTarget,
+        id: String,
+        doc_type: DocType,
+        sha256: BytesN<32>,
+        uri: String,
+    ) -> Result<(), VaultError> {
+        caller.require_auth();
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        if uri.len() == 0 { return Err(VaultError::Invalid); }
+
+        let doc = Document { doc_type: doc_type.clone(), sha256: sha256.clone(), uri, added_by: caller.clone(), added_at: env.ledger().timestamp() };
+        match target.clone() {
+            DocTarget::Receivable => {
+                if caller != admin { return Err(VaultError::Unauthorized); }
+                // KYC é do cadastro, não do título
+                if let DocType::KycDossier = doc_type { return Err(VaultError::Invalid); }
+                let mut r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id.clone())).ok_or(VaultError::NotFound)?;
+                Self::push_document(&mut r.documents, doc)?;
+                env.storage().persistent().set(&DataKey::Receivable(id.clone()), &r);
+            }
+            DocTarget::Cedente | DocTarget::Sacado => {
+                // NF e comprovante de entrega pertencem ao recebível
+                match doc_type {
+                    DocType::Invoice | DocType::DeliveryProof => return Err(VaultError::Invalid),
+                    _ => {}
+                }
+                let key = if let DocTarget::Cedente = target { DataKey::Cedente(id.clone()) } else { DataKey::Sacado(id.clone()) };
+                let mut e: EntityRecord = env.storage().persistent().get(&key).ok_or(VaultError::NotFound)?;
+                if caller != admin {
+                    if e.created_by != caller || !Self::is_consultant(&env, &caller) { return Err(VaultError::Unauthorized); }
+                    if !Self::is_assigned(env.clone(), caller.clone(), e.fund_id.clone()) { return Err(VaultError::NotAssigned); }
+                }
+                Self::push_document(&mut e.documents, doc)?;
+                env.storage().persistent().set(&key, &e);
+            }
+        }
+        env.events().publish((symbol_short!("doc_add"), target, id), sha256);
+        Ok(())
+    }
+    fn push_document(docs: &mut Vec<Document>, doc: Document) -> Result<(), VaultError> {
+        if docs.len() >= MAX_DOCUMENTS { return Err(VaultError::TooManyDocuments); }
+        if docs.iter().any(|d| d.sha256 == doc.sha256) { return Err(VaultError::DuplicateId); }
+        docs.push_back(doc);
+        Ok(())
+    }
+
+    pub fn documents(env: Env, target: DocTarget, id: String) -> Result<Vec<Document>, VaultError> {
+        match target {
+            DocTarget::Receivable => {
+                let r: Receivable = env.storage().persistent().get(&DataKey::Receivable(id)).ok_or(VaultError::NotFound)?;
+                Ok(r.documents)
+            }
+            DocTarget::Cedente => {
+                let e: EntityRecord = env.storage().persistent().get(&DataKey::Cedente(id)).ok_or(VaultError::NotFound)?;
+                Ok(e.documents)
+            }
+            DocTarget::Sacado => {
+                let e: EntityRecord = env.storage().persistent().get(&DataKey::Sacado(id)).ok_or(VaultError::NotFound)?;
+                Ok(e.documents)
+            }
+        }
+    }
+
+    // -------- Timelock --------
+    /// Configura guardião e atraso (pelo menos MIN_DELAY) uma única vez; mudanças posteriores
+    /// passam pelo próprio timelock.
+    pub fn init_timelock(env: Env, guardian: Address, delay: u32) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        if env.storage().instance().has(&DataKey::Guardian) { return Err(VaultError::AlreadyInitialized); }
+        if delay < MIN_DELAY { return Err(VaultError::DelayTooShort); }
+        env.storage().instance().set(&DataKey::Guardian, &guardian);
+        env.storage().instance().set(&DataKey::TimelockDelay, &delay);
+        env.events().publish((symbol_short!("tl_init"), guardian), delay);
+        Ok(())
+    }
+
+    pub fn schedule(env: Env, op: TimelockOp) -> Result<u32, VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let delay: u32 = env.storage().instance().get(&DataKey::TimelockDelay).ok_or(VaultError::NoTimelock)?;
+        match &op {
+            TimelockOp::SetPaymentAsset(fund_id, _) => {
+                if !env.storage().persistent().has(&DataKey::Fund(fund_id.clone())) { return Err(VaultError::NotFound); }
+            }
+            TimelockOp::SetDelay(d) => if *d < MIN_DELAY { return Err(VaultError::DelayTooShort); },
+            _ => {}
+        }
+        let id: u32 = env.storage().instance().get(&DataKey::TimelockCount).unwrap_or(0);
+        env.storage().instance().set(&DataKey::TimelockCount, &(id + 1));
+        let eta = env.ledger().sequence().saturating_add(delay);
+        env.storage().persistent().set(&DataKey::TimelockOp(id), &ScheduledOp { op: op.clone(), eta });
+        env.events().publish((symbol_short!("tl_sched"), id), (op, eta));
+        Ok(id)
+    }
+
+    pub fn execute(env: Env, op_id: u32) -> Result<(), VaultError> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let key = DataKey::TimelockOp(op_id);
+        let scheduled: ScheduledOp = env.storage().persistent().get(&key).ok_or(VaultError::NotFound)?;
+        if env.ledger().sequence() < scheduled.eta { return Err(VaultError::NotReady); }
+        env.storage().persistent().remove(&key);
+
+        match scheduled.op {
+            TimelockOp::SetPaymentAsset(fund_id, asset) => Self::write_payment_asset(&env, fund_id, asset)?,
+            TimelockOp::Upgrade(hash) => env.deployer().update_current_contract_wasm(hash),
+            TimelockOp::SetDelay(delay) => env.storage().instance().set(&DataKey::TimelockDelay, &delay),
+            TimelockOp::SetGuardian(guardian) => env.storage().instance().set(&DataKey::Guardian, &guardian),
+        }
+        env.events().publish((symbol_short!("tl_exec"), op_id), ());
+        Ok(())
+    }
+
+    pub fn cancel(env: Env, op_id: u32) -> Result<(), VaultError> {
+        let guardian: Address = env.storage().instance().get(&DataKey::Guardian).ok_or(VaultError::NoTimelock)?;
+        guardian.require_auth();
+        let key = DataKey::TimelockOp(op_id);
+        if !env.storage().persistent().has(&key) { return Err(VaultError::NotFound); }
+        env.storage().persistent().remove(&key);
+        env.events().publish((symbol_short!("tl_cancel"), op_id), guardian);
+        Ok(())
+    }
+
+    pub fn scheduled_op(env: Env, op_id: u32) -> Option<ScheduledOp> {
+        env.storage().persistent().get(&DataKey::TimelockOp(op_id))
+    }
+    pub fn guardian(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Guardian)
+    }
+
+    // -------- Views --------
+    pub fn get_receivable(env: Env, id: String) -> Result<Receivable, VaultError> {
+        env.storage().persistent().get(&DataKey::Receivable(id)).ok_or(VaultError::NotFound)
+    }
+    pub fn get_cedente(env: Env, id: String) -> Result<EntityRecord, VaultError> {
+        env.storage().persistent().get(&DataKey::Cedente(id)).ok_or(VaultError::NotFound)
+    }
+    pub fn get_sacado(env: Env, id: String) -> Result<EntityRecord, VaultError> {
+        env.storage().persistent().get(&DataKey::Sacado(id)).ok_or(VaultError::NotFound)
+    }
+    pub fn get_fund(env: Env, id: String) -> Result<FundRecord, VaultError> {
+        env.storage().persistent().get(&DataKey::Fund(id)).ok_or(VaultError::NotFound)
+    }
+    pub fn consultant_production(env: Env, consultant: Address, fund_id: String) -> Production {
+        env.storage().persistent().get(&DataKey::Production(consultant, fund_id)).unwrap_or_default()
+    }
+    pub fn get_receivables_count(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::ReceivableCount).unwrap_or(0)
+    }
+    pub fn get_total_paid(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::TotalPaid).unwrap_or(0)
+    }
+    pub fn get_fund_token_address(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::FundTokenAddress).unwrap()
+    }
+    pub fn get_admin(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Admin).unwrap()
+    }
+}
+
+mod test;
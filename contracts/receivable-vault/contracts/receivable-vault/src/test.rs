@@ -1,21 +1,1619 @@
 #![cfg(test)]
+extern crate std;
 
 use super::*;
-use soroban_sdk::{vec, Env, String};
+use soroban_sdk::{
+    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation, Ledger as _, MockAuth, MockAuthInvoke},
+    token, vec, BytesN, Env, IntoVal, String, Symbol,
+};
+
+mod mock_token {
+    use soroban_sdk::{contract, contractimpl, contracttype, Address, Env};
+
+    #[contracttype]
+    enum Key { Balance(Address), Whitelist(Address), Supply, MaxSupply, Paused }
+
+    /// FundToken mínimo: só o que o vault consulta.
+    #[contract]
+    pub struct MockToken;
+
+    #[contractimpl]
+    impl MockToken {
+        pub fn set_holder(env: Env, addr: Address, balance: i128, whitelisted: bool) {
+            let old: i128 = env.storage().persistent().get(&Key::Balance(addr.clone())).unwrap_or(0);
+            let supply: i128 = env.storage().instance().get(&Key::Supply).unwrap_or(0);
+            env.storage().instance().set(&Key::Supply, &(supply - old + balance));
+            env.storage().persistent().set(&Key::Balance(addr.clone()), &balance);
+            env.storage().persistent().set(&Key::Whitelist(addr), &whitelisted);
+        }
+        pub fn set_pause(env: Env, paused: bool) { env.storage().instance().set(&Key::Paused, &paused); }
+        pub fn set_max_supply(env: Env, max: i128) { env.storage().instance().set(&Key::MaxSupply, &max); }
+        pub fn mint_by(env: Env, minter: Address, to: Address, amount: i128) {
+            minter.require_auth();
+            let bal = Self::balance(env.clone(), to.clone());
+            let wl = Self::is_whitelisted(env.clone(), to.clone());
+            Self::set_holder(env, to, bal + amount, wl);
+        }
+        pub fn transfer(env: Env, from: Address, to: Address, amount: i128) -> bool {
+            from.require_auth();
+            let (from_bal, to_bal) = (Self::balance(env.clone(), from.clone()), Self::balance(env.clone(), to.clone()));
+            let (from_wl, to_wl) = (Self::is_whitelisted(env.clone(), from.clone()), Self::is_whitelisted(env.clone(), to.clone()));
+            assert!(to_wl && from_bal >= amount);
+            Self::set_holder(env.clone(), from, from_bal - amount, from_wl);
+            Self::set_holder(env, to, to_bal + amount, to_wl);
+            true
+        }
+        pub fn burn(env: Env, from: Address, amount: i128) {
+            from.require_auth();
+            let bal = Self::balance(env.clone(), from.clone());
+            let wl = Self::is_whitelisted(env.clone(), from.clone());
+            Self::set_holder(env, from, bal - amount, wl);
+        }
+        pub fn max_supply(env: Env) -> i128 { env.storage().instance().get(&Key::MaxSupply).unwrap_or(i128::MAX) }
+        pub fn balance(env: Env, addr: Address) -> i128 { env.storage().persistent().get(&Key::Balance(addr)).unwrap_or(0) }
+        pub fn total_supply(env: Env) -> i128 { env.storage().instance().get(&Key::Supply).unwrap_or(0) }
+        pub fn is_paused(env: Env) -> bool { env.storage().instance().get(&Key::Paused).unwrap_or(false) }
+        pub fn is_whitelisted(env: Env, address: Address) -> bool { env.storage().persistent().get(&Key::Whitelist(address)).unwrap_or(false) }
+        pub fn reward_per_share(_env: Env) -> i128 { 0 }
+        pub fn take_reward(_env: Env, _holder: Address) -> i128 { 0 }
+    }
+}
+use mock_token::{MockToken, MockTokenClient};
+
+// Limite de CPU por transação usado pelo orçamento padrão do Env de testes (= rede).
+const CPU_LIMIT: u64 = 100_000_000;
+
+struct Setup<'a> {
+    env: Env,
+    client: ReceivableVaultClient<'a>,
+    token: MockTokenClient<'a>,
+}
+
+fn s(env: &Env, v: &str) -> String { String::from_str(env, v) }
+
+fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(ReceivableVault, ());
+    let client = ReceivableVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let token = env.register(MockToken, ());
+    client.initialize(&admin, &token);
+
+    // cadastros já aprovados, gravados direto no storage
+    let consultant = Address::generate(&env);
+    env.as_contract(&contract_id, || {
+        let fund = FundRecord {
+            id: s(&env, "FUND-1"), name: s(&env, "Fundo"), created_by: consultant.clone(), status: Status::Approved,
+            approved_by: None, approved_at: 0, rejection_reason: None, token: token.clone(),
+        };
+        env.storage().persistent().set(&DataKey::Fund(s(&env, "FUND-1")), &fund);
+        env.storage().persistent().set(&DataKey::Cedente(s(&env, "CED-1")), &entity(&env, "CED-1", &consultant, Status::Approved));
+        env.storage().persistent().set(&DataKey::Sacado(s(&env, "SAC-1")), &entity(&env, "SAC-1", &consultant, Status::Approved));
+    });
+    Setup { token: MockTokenClient::new(&env, &token), env, client }
+}
+
+fn entity(env: &Env, id: &str, created_by: &Address, status: Status) -> EntityRecord {
+    EntityRecord {
+        id: s(env, id), name: s(env, id), fund_id: s(env, "FUND-1"), created_by: created_by.clone(), status,
+        approved_by: None, approved_at: 0, rejection_reason: None, documents: Vec::new(env),
+    }
+}
+
+fn rid(env: &Env, i: u32) -> String { String::from_str(env, &std::format!("R-{i:04}")) }
+
+fn inputs(env: &Env, n: u32) -> Vec<ReceivableInput> {
+    let debtor = Address::generate(env);
+    let mut v = vec![env];
+    for i in 0..n {
+        v.push_back(ReceivableInput {
+            id: rid(env, i),
+            fund_id: s(env, "FUND-1"),
+            cedente_id: s(env, "CED-1"),
+            sacado_id: s(env, "SAC-1"),
+            face_value: 1_000_0000000,
+            due_date: 1_800_000_000,
+            debtor_address: debtor.clone(),
+        });
+    }
+    v
+}
+
+/// Recebíveis R-0000.. com os valores de face dados.
+fn with_faces(env: &Env, faces: &[i128]) -> Vec<ReceivableInput> {
+    let mut v = inputs(env, faces.len() as u32);
+    for (i, face) in faces.iter().enumerate() {
+        let mut it = v.get(i as u32).unwrap();
+        it.face_value = *face;
+        v.set(i as u32, it);
+    }
+    v
+}
+
+fn payments(env: &Env, n: u32) -> Vec<(String, i128)> {
+    let mut v = vec![env];
+    for i in 0..n { v.push_back((rid(env, i), 1_000_0000000)); }
+    v
+}
+
+fn hash(env: &Env, i: u8) -> BytesN<32> { BytesN::from_array(env, &[i; 32]) }
+
+#[test]
+fn documents_follow_role_and_type_rules() {
+    let t = setup();
+    let admin = t.client.get_admin();
+    let uri = s(&t.env, "ipfs://doc");
+    let (ced, rcv) = (s(&t.env, "CED-1"), rid(&t.env, 0));
+    t.client.register_receivables(&inputs(&t.env, 1));
+    let author = t.client.get_cedente(&ced).created_by;
+    let stranger = Address::generate(&t.env);
+
+    // título: só o gestor, e sem dossiê KYC
+    t.client.attach_document(&admin, &DocTarget::Receivable, &rcv, &DocType::Invoice, &hash(&t.env, 1), &uri);
+    assert_eq!(t.client.try_attach_document(&author, &DocTarget::Receivable, &rcv, &DocType::Invoice, &hash(&t.env, 2), &uri), Err(Ok(VaultError::Unauthorized)));
+    assert_eq!(t.client.try_attach_document(&admin, &DocTarget::Receivable, &rcv, &DocType::KycDossier, &hash(&t.env, 2), &uri), Err(Ok(VaultError::Invalid)));
+    assert_eq!(t.client.try_attach_document(&admin, &DocTarget::Receivable, &rcv, &DocType::Invoice, &hash(&t.env, 1), &uri), Err(Ok(VaultError::DuplicateId)));
+    assert_eq!(t.client.try_attach_document(&admin, &DocTarget::Receivable, &rcv, &DocType::Invoice, &hash(&t.env, 2), &s(&t.env, "")), Err(Ok(VaultError::Invalid)));
+
+    // cadastro: o gestor ou o consultor autor (ainda na whitelist e designado para o fundo),
+    // sem NF nem comprovante
+    assert_eq!(t.client.try_attach_document(&author, &DocTarget::Cedente, &ced, &DocType::KycDossier, &hash(&t.env, 3), &uri), Err(Ok(VaultError::Unauthorized)));
+    t.client.consultant_add(&author);
+    assert_eq!(t.client.try_attach_document(&author, &DocTarget::Cedente, &ced, &DocType::KycDossier, &hash(&t.env, 3), &uri), Err(Ok(VaultError::NotAssigned)));
+    t.client.consultant_assign(&author, &s(&t.env, "FUND-1"));
+    t.client.attach_document(&author, &DocTarget::Cedente, &ced, &DocType::KycDossier, &hash(&t.env, 3), &uri);
+    assert_eq!(t.client.try_attach_document(&stranger, &DocTarget::Cedente, &ced, &DocType::Assignment, &hash(&t.env, 4), &uri), Err(Ok(VaultError::Unauthorized)));
+    assert_eq!(t.client.try_attach_document(&admin, &DocTarget::Sacado, &s(&t.env, "SAC-1"), &DocType::DeliveryProof, &hash(&t.env, 4), &uri), Err(Ok(VaultError::Invalid)));
+    assert_eq!(t.client.try_attach_document(&admin, &DocTarget::Sacado, &s(&t.env, "SAC-X"), &DocType::Assignment, &hash(&t.env, 4), &uri), Err(Ok(VaultError::NotFound)));
+
+    let docs = t.client.documents(&DocTarget::Cedente, &ced);
+    assert_eq!(docs.len(), 1);
+    let d = docs.get(0).unwrap();
+    assert!(matches!(d.doc_type, DocType::KycDossier));
+    assert_eq!((d.sha256, d.added_by), (hash(&t.env, 3), author));
+
+    for i in 1..MAX_DOCUMENTS as u8 {
+        t.client.attach_document(&admin, &DocTarget::Receivable, &rcv, &DocType::DeliveryProof, &hash(&t.env, 100 + i), &uri);
+    }
+    assert_eq!(t.client.documents(&DocTarget::Receivable, &rcv).len(), MAX_DOCUMENTS);
+    assert_eq!(t.client.try_attach_document(&admin, &DocTarget::Receivable, &rcv, &DocType::Assignment, &hash(&t.env, 99), &uri), Err(Ok(VaultError::TooManyDocuments)));
+}
+
+#[test]
+fn register_batch_is_all_or_nothing() {
+    let t = setup();
+    let mut batch = inputs(&t.env, 5);
+    let mut bad = batch.get(3).unwrap();
+    bad.sacado_id = s(&t.env, "SAC-X");
+    batch.set(3, bad);
+
+    assert_eq!(t.client.register_receivables(&batch), BatchResult::Failed(3, VaultError::NotFound as u32));
+    assert_eq!(t.client.get_receivables_count(), 0);
+
+    assert_eq!(t.client.register_receivables(&inputs(&t.env, 5)), BatchResult::Done(5));
+    assert_eq!(t.client.get_receivables_count(), 5);
+}
+
+#[test]
+fn batch_rejects_duplicates_and_oversize() {
+    let t = setup();
+    let mut batch = inputs(&t.env, 3);
+    batch.push_back(batch.get(1).unwrap());
+    assert_eq!(t.client.register_receivables(&batch), BatchResult::Failed(3, VaultError::DuplicateId as u32));
+    assert_eq!(t.client.try_register_receivables(&inputs(&t.env, MAX_BATCH + 1)), Err(Ok(VaultError::BatchTooLarge)));
+
+    t.client.register_receivables(&inputs(&t.env, 3));
+    let mut pays = payments(&t.env, 3);
+    pays.push_back((rid(&t.env, 0), 1));
+    assert_eq!(t.client.mark_paid_batch(&pays), BatchResult::Failed(3, VaultError::DuplicateId as u32));
+    assert_eq!(t.client.get_total_paid(), 0);
+}
+
+#[test]
+fn mark_paid_batch_pays_every_item() {
+    let t = setup();
+    t.client.register_receivables(&inputs(&t.env, 4));
+    assert_eq!(t.client.mark_paid_batch(&payments(&t.env, 4)), BatchResult::Done(4));
+    assert_eq!(t.client.get_total_paid(), 4 * 1_000_0000000);
+    // segunda baixa do mesmo título falha no índice 0 e não altera nada
+    assert_eq!(t.client.mark_paid_batch(&payments(&t.env, 1)), BatchResult::Failed(0, VaultError::BadStatus as u32));
+}
+
+// Limites de rede por transação (protocolo 22).
+const READ_ENTRIES_LIMIT: u32 = 40; // leitura + escrita
+const WRITE_ENTRIES_LIMIT: u32 = 25;
+const READ_BYTES_LIMIT: u32 = 200 * 1024;
+const WRITE_BYTES_LIMIT: u32 = 129 * 1024;
+
+struct Footprint { cpu: u64, read_entries: u32, write_entries: u32, read_bytes: u32, write_bytes: u32 }
+
+/// Custo da última invocação (CPU do orçamento, entradas e bytes do footprint).
+fn footprint(env: &Env) -> Footprint {
+    let r = env.cost_estimate().resources();
+    Footprint {
+        cpu: env.cost_estimate().budget().cpu_instruction_cost(),
+        read_entries: r.read_entries,
+        write_entries: r.write_entries,
+        read_bytes: r.read_bytes,
+        write_bytes: r.write_bytes,
+    }
+}
+
+/// Confere que a última invocação cabe em todos os limites da transação.
+fn assert_within_limits(env: &Env) {
+    let f = footprint(env);
+    assert!(f.cpu < CPU_LIMIT, "CPU acima do limite");
+    assert!(f.write_entries <= WRITE_ENTRIES_LIMIT, "escritas acima do limite");
+    assert!(f.read_entries + f.write_entries <= READ_ENTRIES_LIMIT, "leituras acima do limite");
+    assert!(f.read_bytes <= READ_BYTES_LIMIT && f.write_bytes <= WRITE_BYTES_LIMIT, "bytes acima do limite");
+}
+
+fn register_cost(n: u32) -> Footprint {
+    let t = setup();
+    let batch = inputs(&t.env, n);
+    t.env.cost_estimate().budget().reset_default();
+    t.client.register_receivables(&batch);
+    footprint(&t.env)
+}
+
+fn mark_paid_cost(n: u32) -> Footprint {
+    let t = setup();
+    t.client.register_receivables(&inputs(&t.env, n));
+    let pays = payments(&t.env, n);
+    t.env.cost_estimate().budget().reset_default();
+    t.client.mark_paid_batch(&pays);
+    footprint(&t.env)
+}
+
+/// Maior lote que cabe num limite, extrapolado linearmente do custo de 1 e de `n` itens.
+fn fits(one: u64, full: u64, n: u32, limit: u64) -> u64 {
+    if one > limit { return 0; }
+    let per_item = full.saturating_sub(one) / (n as u64 - 1);
+    if per_item == 0 { return u64::MAX; }
+    (limit - one) / per_item + 1
+}
+
+/// Maior lote seguro em todos os limites da transação: CPU, entradas lidas (leitura + escrita),
+/// entradas escritas e bytes.
+fn max_safe_batch(one: &Footprint, full: &Footprint, n: u32) -> u64 {
+    let entries = |f: &Footprint| (f.read_entries + f.write_entries) as u64;
+    [
+        fits(one.cpu, full.cpu, n, CPU_LIMIT),
+        fits(entries(one), entries(full), n, READ_ENTRIES_LIMIT as u64),
+        fits(one.write_entries as u64, full.write_entries as u64, n, WRITE_ENTRIES_LIMIT as u64),
+        fits(one.read_bytes as u64, full.read_bytes as u64, n, READ_BYTES_LIMIT as u64),
+        fits(one.write_bytes as u64, full.write_bytes as u64, n, WRITE_BYTES_LIMIT as u64),
+    ]
+    .into_iter()
+    .min()
+    .unwrap()
+}
+
+fn check_budget(one: Footprint, full: Footprint) {
+    assert!(full.cpu < CPU_LIMIT / 2, "MAX_BATCH deve usar menos da metade do orçamento");
+    assert!(full.write_entries <= WRITE_ENTRIES_LIMIT, "MAX_BATCH excede o limite de escritas");
+    assert!(full.read_entries + full.write_entries <= READ_ENTRIES_LIMIT, "MAX_BATCH excede o limite de leituras");
+    assert!(full.read_bytes <= READ_BYTES_LIMIT && full.write_bytes <= WRITE_BYTES_LIMIT);
+    assert!(max_safe_batch(&one, &full, MAX_BATCH) >= MAX_BATCH as u64, "MAX_BATCH acima do maior lote seguro");
+}
+
+#[test]
+fn bench_register_receivables() {
+    check_budget(register_cost(1), register_cost(MAX_BATCH));
+}
+
+#[test]
+fn bench_mark_paid_batch() {
+    check_budget(mark_paid_cost(1), mark_paid_cost(MAX_BATCH));
+}
+
+fn clear_cost(n: u32) -> Footprint {
+    let t = setup();
+    let id = paid_receivable(&t, 1_000 * n as i128);
+    let hs = holders(&t, &std::vec![(1, true); n as usize]);
+    t.token.set_pause(&true);
+    t.client.start_distribution(&id, &hs);
+    t.client.process_distribution(&id, &0, &n);
+    assert!(t.client.finalize_distribution(&id));
+    t.env.cost_estimate().budget().reset_default();
+    assert_eq!(t.client.clear_distribution(&id, &n), 0);
+    footprint(&t.env)
+}
+
+#[test]
+fn bench_clear_distribution() {
+    check_budget(clear_cost(1), clear_cost(MAX_BATCH));
+}
+
+/// Registra e paga um recebível de `amount`, pronto para distribuir.
+fn paid_receivable(t: &Setup, amount: i128) -> String {
+    let mut batch = inputs(&t.env, 1);
+    let mut it = batch.get(0).unwrap();
+    it.face_value = amount;
+    batch.set(0, it);
+    t.client.register_receivables(&batch);
+    t.client.mark_paid(&rid(&t.env, 0), &amount);
+    rid(&t.env, 0)
+}
+
+fn holders(t: &Setup, balances: &[(i128, bool)]) -> Vec<Address> {
+    let mut v = vec![&t.env];
+    for (bal, wl) in balances {
+        let h = Address::generate(&t.env);
+        t.token.set_holder(&h, bal, wl);
+        v.push_back(h);
+    }
+    v
+}
+
+#[test]
+fn distribute_rejects_duplicate_holders() {
+    let t = setup();
+    let id = paid_receivable(&t, 1_000);
+    let mut hs = holders(&t, &[(10, true), (20, true)]);
+    hs.push_back(hs.get(0).unwrap());
+    assert_eq!(t.client.try_distribute(&id, &hs), Err(Ok(VaultError::DuplicateHolder)));
+}
+
+#[test]
+fn distribute_rejects_incomplete_holder_list() {
+    let t = setup();
+    let id = paid_receivable(&t, 1_000);
+    let mut hs = holders(&t, &[(10, true), (20, true), (30, true)]);
+    hs.pop_back();
+    assert_eq!(t.client.try_distribute(&id, &hs), Err(Ok(VaultError::IncompleteHolders)));
+}
+
+#[test]
+fn non_whitelisted_holders_follow_fund_policy() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let id = paid_receivable(&t, 1_000);
+    let hs = holders(&t, &[(50, true), (50, false)]);
+    let blocked = hs.get(1).unwrap();
+
+    // padrão: retém no vault
+    t.client.distribute(&id, &hs);
+    assert_eq!(t.client.escrowed(&fund, &blocked), 500);
+    assert_eq!(t.client.try_release_escrow(&fund, &blocked), Err(Ok(VaultError::NotWhitelisted)));
+    t.token.set_holder(&blocked, &50, &true);
+    assert_eq!(t.client.release_escrow(&fund, &blocked), 500);
+    assert_eq!(t.client.escrowed(&fund, &blocked), 0);
+
+    // Skip: não paga e contabiliza como não alocado
+    t.token.set_holder(&blocked, &50, &false);
+    t.client.set_holder_policy(&fund, &HolderPolicy::Skip);
+    let mut batch = with_faces(&t.env, &[1_000, 1_000]);
+    batch.pop_front();
+    t.client.register_receivables(&batch);
+    t.client.mark_paid(&rid(&t.env, 1), &1_000);
+    t.client.distribute(&rid(&t.env, 1), &hs);
+    assert_eq!(t.client.unallocated(&fund), 500);
+}
+
+#[test]
+fn paginated_distribution_requires_full_holder_set() {
+    let t = setup();
+    let id = paid_receivable(&t, 1_000);
+    let hs = holders(&t, &[(10, true), (20, true), (30, true)]);
+    assert_eq!(t.client.try_start_distribution(&id, &hs), Err(Ok(VaultError::NotPaused)));
+    t.token.set_pause(&true);
+
+    let mut first = hs.clone();
+    first.pop_back();
+    t.client.start_distribution(&id, &first);
+    assert_eq!(t.client.try_add_distribution_holders(&id, &first.slice(0..1)), Err(Ok(VaultError::DuplicateHolder)));
+    assert_eq!(t.client.process_distribution(&id, &0, &MAX_BATCH), 2);
+    assert_eq!(t.client.try_finalize_distribution(&id), Err(Ok(VaultError::IncompleteHolders)));
+
+    t.client.add_distribution_holders(&id, &hs.slice(2..3));
+    assert_eq!(t.client.process_distribution(&id, &2, &1), 3);
+    assert_eq!(t.client.get_distribution(&id).balance_sum, 60);
+    t.client.finalize_distribution(&id);
+    assert!(matches!(t.client.get_receivable(&id).status, ReceivableStatus::Distributed));
+
+    // a execução é apagada: um segundo finalize não paga o resíduo de novo
+    assert_eq!(t.client.try_get_distribution(&id), Err(Ok(VaultError::NotFound)));
+    assert_eq!(t.client.try_finalize_distribution(&id), Err(Ok(VaultError::BadStatus)));
+    // a lista de cotistas sai em lotes
+    assert_eq!(t.client.clear_distribution(&id, &2), 1);
+    assert_eq!(t.client.clear_distribution(&id, &2), 0);
+    assert_eq!(t.client.try_clear_distribution(&id, &2), Err(Ok(VaultError::NotFound)));
+}
 
 #[test]
-fn test() {
+fn paginated_residue_beyond_candidates_follows_largest_remainder() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    // 70 cotistas com 1 cota e 69 stroops: todos têm o mesmo resto, então o resíduo vai
+    // para as posições 0..69 e a última fica sem nada
+    let id = paid_receivable(&t, 69);
+    let hs = holders(&t, &[(1, false); 70]);
+    t.token.set_pause(&true);
+    t.client.start_distribution(&id, &hs.slice(0..MAX_BATCH));
+    for start in (MAX_BATCH..70).step_by(MAX_BATCH as usize) {
+        t.client.add_distribution_holders(&id, &hs.slice(start..start + MAX_BATCH));
+    }
+    // cada chamada precisa caber nos limites da transação
+    let process_all = || {
+        for cursor in (0..70).step_by(MAX_BATCH as usize) {
+            t.client.process_distribution(&id, &cursor, &MAX_BATCH);
+            assert_within_limits(&t.env);
+        }
+    };
+    // finaliza até pagar os candidatos guardados; retorna se terminou
+    let finalize = || loop {
+        let done = t.client.finalize_distribution(&id);
+        assert_within_limits(&t.env);
+        if done || t.client.get_distribution(&id).cursor == 0 { return done; }
+    };
+
+    process_all();
+    assert!(!finalize());
+    assert_eq!(t.client.get_distribution(&id).distributed, 64);
+    assert_eq!(t.client.try_cancel_distribution(&id), Err(Ok(VaultError::BadStatus)));
+    process_all();
+    assert!(finalize());
+    while t.client.clear_distribution(&id, &MAX_BATCH) > 0 {
+        assert_within_limits(&t.env);
+    }
+    assert_within_limits(&t.env);
+
+    for (i, h) in hs.iter().enumerate() {
+        assert_eq!(t.client.escrowed(&fund, &h), if i < 69 { 1 } else { 0 });
+    }
+    assert!(matches!(t.client.get_receivable(&id).status, ReceivableStatus::Distributed));
+}
+
+/// Gerador pseudoaleatório determinístico (LCG) para os testes de propriedade.
+struct Lcg(u64);
+impl Lcg {
+    fn next(&mut self, max: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) % max
+    }
+}
+
+#[test]
+fn allocate_always_sums_to_amount() {
+    let env = Env::default();
+    let mut rng = Lcg(42);
+    for _ in 0..500 {
+        let n = 1 + rng.next(40) as u32;
+        let mut balances = vec![&env];
+        for _ in 0..n {
+            // mistura saldos zerados, pequenos e grandes
+            let b = match rng.next(4) { 0 => 0, 1 => rng.next(10) as i128, _ => rng.next(1_000_000_000_000) as i128 };
+            balances.push_back(b);
+        }
+        if balances.iter().all(|b| b == 0) { balances.set(0, 1); }
+        let amount = rng.next(10_000_000_000_000) as i128;
+
+        let (shares, extra) = allocate(&env, &balances, amount).unwrap();
+        let total: i128 = balances.iter().sum();
+        assert_eq!(shares.iter().sum::<i128>(), amount);
+        for i in 0..n {
+            let floor = balances.get(i).unwrap() * amount / total;
+            let e = extra.get(i).unwrap();
+            assert!(e == 0 || e == 1);
+            assert_eq!(shares.get(i).unwrap(), floor + e);
+        }
+        // quem recebeu resíduo nunca tem resto menor que quem não recebeu
+        for i in 0..n {
+            for j in 0..n {
+                if extra.get(i).unwrap() == 1 && extra.get(j).unwrap() == 0 {
+                    let (bi, bj) = (balances.get(i).unwrap(), balances.get(j).unwrap());
+                    assert!(ranks_before(bi * amount % total, bi, i, bj * amount % total, bj, j));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn allocate_tiebreak_is_deterministic() {
     let env = Env::default();
-    let contract_id = env.register(Contract, ());
-    let client = ContractClient::new(&env, &contract_id);
-
-    let words = client.hello(&String::from_str(&env, "Dev"));
-    assert_eq!(
-        words,
-        vec![
-            &env,
-            String::from_str(&env, "Hello"),
-            String::from_str(&env, "Dev"),
-        ]
-    );
+    // três saldos iguais, resíduo de 1 stroop: vai para a primeira posição
+    let (shares, _) = allocate(&env, &vec![&env, 1, 1, 1], 100).unwrap();
+    assert_eq!(shares, vec![&env, 34, 33, 33]);
+    // mesmo resto: o maior saldo tem prioridade
+    let (shares, _) = allocate(&env, &vec![&env, 1, 3], 2).unwrap();
+    assert_eq!(shares, vec![&env, 0, 2]);
+    // mesmo resto e mesmo saldo: a menor posição
+    let (shares, _) = allocate(&env, &vec![&env, 2, 1, 2], 1).unwrap();
+    assert_eq!(shares, vec![&env, 1, 0, 0]);
+}
+
+#[test]
+fn preview_matches_distribute_without_changing_state() {
+    let t = setup();
+    let id = paid_receivable(&t, 100);
+    let hs = holders(&t, &[(1, true), (1, true), (1, true)]);
+
+    let preview = t.client.preview_distribution(&id, &hs);
+    assert_eq!(preview.shares, vec![&t.env, (hs.get(0).unwrap(), 34), (hs.get(1).unwrap(), 33), (hs.get(2).unwrap(), 33)]);
+    assert_eq!(preview.residue, vec![&t.env, (hs.get(0).unwrap(), 1)]);
+    assert!(matches!(t.client.get_receivable(&id).status, ReceivableStatus::Paid));
+
+    t.client.distribute(&id, &hs);
+    assert_eq!(t.client.try_preview_distribution(&id, &hs), Err(Ok(VaultError::BadStatus)));
+}
+
+/// Troca o token do FUND-1 por um FundToken real, com o vault como distributor.
+fn real_token<'a>(t: &Setup<'a>) -> fund_token::FundTokenClient<'a> {
+    let id = t.env.register(fund_token::FundToken, ());
+    let ft = fund_token::FundTokenClient::new(&t.env, &id);
+    ft.initialize(&Address::generate(&t.env), &s(&t.env, "Cota"), &s(&t.env, "FIDC1"), &1_000_000_0000000);
+    ft.set_distributor(&t.client.address);
+    let fund = s(&t.env, "FUND-1");
+    t.env.as_contract(&t.client.address, || {
+        let mut f: FundRecord = t.env.storage().persistent().get(&DataKey::Fund(fund.clone())).unwrap();
+        f.token = id.clone();
+        t.env.storage().persistent().set(&DataKey::Fund(fund.clone()), &f);
+    });
+    ft
+}
+
+#[test]
+fn claimable_distribution_pays_through_the_fund_token_accumulator() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let ft = real_token(&t);
+    let (a, b) = (Address::generate(&t.env), Address::generate(&t.env));
+    for h in [&a, &b] { ft.whitelist_add(h); }
+    ft.mint(&a, &1);
+    ft.mint(&b, &2);
+
+    let id = paid_receivable(&t, 10);
+    let payer = Address::generate(&t.env);
+    assert_eq!(t.client.try_distribute_claimable(&payer, &id), Err(Ok(VaultError::NoPaymentAsset)));
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    token::StellarAssetClient::new(&t.env, &asset).mint(&payer, &10);
+    t.client.set_payment_asset(&fund, &asset);
+    t.client.distribute_claimable(&payer, &id);
+    assert_eq!(t.client.try_distribute_claimable(&payer, &id), Err(Ok(VaultError::BadStatus)));
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&t.client.address), 10);
+    assert_eq!(t.client.total_claimable(&fund), 10);
+    assert_eq!((t.client.claimable(&fund, &a), t.client.claimable(&fund, &b)), (3, 6));
+
+    // b transfere antes de sacar: o já lançado continua dele
+    ft.transfer(&b, &a, &2);
+    assert_eq!(t.client.claim(&b, &fund), 6);
+    assert_eq!(t.client.claim(&b, &fund), 0);
+    ft.whitelist_remove(&a);
+    assert_eq!(t.client.try_claim(&a, &fund), Err(Ok(VaultError::NotWhitelisted)));
+    ft.whitelist_add(&a);
+    assert_eq!(t.client.claim(&a, &fund), 3);
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&b), 6);
+    assert_eq!(t.client.total_claimable(&fund), 1);
+}
+
+fn strkey(a: &Address) -> std::string::String {
+    let s = a.to_string();
+    let mut buf = [0u8; 56];
+    s.copy_into_slice(&mut buf[..s.len() as usize]);
+    std::str::from_utf8(&buf[..s.len() as usize]).unwrap().into()
+}
+
+#[test]
+fn merkle_claims_verify_proofs_from_companion_crate() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    let funder = Address::generate(&t.env);
+    token::StellarAssetClient::new(&t.env, &asset).mint(&funder, &60);
+    t.client.set_payment_asset(&fund, &asset);
+
+    let hs = holders(&t, &[(1, true), (1, true), (1, true)]);
+    let keys: std::vec::Vec<_> = hs.iter().map(|h| strkey(&h)).collect();
+    let snapshot: std::vec::Vec<(&str, i128)> = keys.iter().map(|k| k.as_str()).zip([10, 20, 30]).collect();
+    let tree = distribution_merkle::MerkleTree::from_strkeys(&snapshot).unwrap();
+    let proof = |i: usize| {
+        let mut v = vec![&t.env];
+        for p in tree.proof(i).unwrap() { v.push_back(BytesN::from_array(&t.env, &p)); }
+        v
+    };
+
+    let dist = s(&t.env, "2026-09");
+    let root = BytesN::from_array(&t.env, &tree.root());
+    // o total sai de quem publica: sem saldo, nada é publicado
+    assert!(t.client.try_publish_merkle_root(&funder, &dist, &fund, &None, &root, &61, &1_000).is_err());
+    t.client.publish_merkle_root(&funder, &dist, &fund, &None, &root, &60, &1_000);
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&t.client.address), 60);
+
+    let (h0, h1) = (hs.get(0).unwrap(), hs.get(1).unwrap());
+    assert_eq!(t.client.claim_merkle(&h0, &dist, &10, &proof(0)), 10);
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&h0), 10);
+    assert_eq!(t.client.try_claim_merkle(&h0, &dist, &10, &proof(0)), Err(Ok(VaultError::AlreadyClaimed)));
+    assert_eq!(t.client.try_claim_merkle(&h1, &dist, &30, &proof(1)), Err(Ok(VaultError::InvalidProof)));
+    assert_eq!(t.client.try_sweep_merkle(&dist, &h0), Err(Ok(VaultError::NotExpired)));
+
+    t.env.ledger().with_mut(|l| l.timestamp = 1_001);
+    assert_eq!(t.client.try_claim_merkle(&h1, &dist, &20, &proof(1)), Err(Ok(VaultError::Expired)));
+    let treasury = Address::generate(&t.env);
+    assert_eq!(t.client.sweep_merkle(&dist, &treasury), 50);
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&treasury), 50);
+}
+
+#[test]
+fn expired_escrow_lots_are_swept_into_reserve() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.register_receivables(&with_faces(&t.env, &[1_000; 3]));
+    for i in 0..3 { t.client.mark_paid(&rid(&t.env, i), &1_000); }
+    let hs = holders(&t, &[(50, false), (50, true)]);
+    let (x, y) = (hs.get(0).unwrap(), hs.get(1).unwrap());
+
+    // x retido com prazo de 100s
+    t.client.set_claim_window(&fund, &100);
+    t.client.distribute(&rid(&t.env, 0), &hs);
+
+    // prazo encurtado: y, retido depois, vence antes de x
+    t.env.ledger().with_mut(|l| l.timestamp = 20);
+    t.client.set_claim_window(&fund, &10);
+    t.token.set_holder(&x, &50, &true);
+    t.token.set_holder(&y, &50, &false);
+    t.client.distribute(&rid(&t.env, 1), &hs);
+
+    // nova retenção de x tem prazo próprio, não herda o da primeira
+    t.env.ledger().with_mut(|l| l.timestamp = 25);
+    t.token.set_holder(&x, &50, &false);
+    t.token.set_holder(&y, &50, &true);
+    t.client.distribute(&rid(&t.env, 2), &hs);
+    let open = t.client.unclaimed_balances(&fund);
+    let ex = open.get(0).unwrap();
+    assert_eq!((ex.amount, ex.expires_at, ex.lots.len()), (1_000, 35, 2));
+    assert_eq!(t.client.sweep_unclaimed(&fund, &0, &MAX_BATCH, &SweepTarget::Reserve), (0, 2));
+
+    t.env.ledger().with_mut(|l| l.timestamp = 40);
+    assert_eq!(t.client.sweep_unclaimed(&fund, &0, &MAX_BATCH, &SweepTarget::Reserve), (1_000, 1));
+    assert_eq!((t.client.reserve_balance(&fund), t.client.reserve_backed(&fund)), (1_000, 1_000));
+    assert_eq!(t.client.escrowed(&fund, &x), 500);
+    assert_eq!(t.client.escrowed(&fund, &y), 0);
+
+    t.token.set_holder(&x, &50, &true);
+    assert_eq!(t.client.release_escrow(&fund, &x), 500);
+    assert_eq!(t.client.unclaimed_balances(&fund).len(), 0);
+}
+
+#[test]
+fn unclaimed_rewards_and_escrow_expire_on_the_claim_window() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let ft = real_token(&t);
+    let (a, b) = (Address::generate(&t.env), Address::generate(&t.env));
+    for h in [&a, &b] {
+        ft.whitelist_add(h);
+        ft.mint(h, &1);
+    }
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    let (funder, payer) = (Address::generate(&t.env), Address::generate(&t.env));
+    token::StellarAssetClient::new(&t.env, &asset).mint(&payer, &14);
+    token::StellarAssetClient::new(&t.env, &asset).mint(&funder, &3);
+    t.client.set_payment_asset(&fund, &asset);
+    t.client.register_receivables(&with_faces(&t.env, &[10, 4, 6]));
+    assert_eq!(t.client.try_sweep_rewards(&fund, &vec![&t.env, a.clone()], &SweepTarget::Reserve), Err(Ok(VaultError::Invalid)));
+    t.client.set_claim_window(&fund, &100);
+
+    t.client.mark_paid(&rid(&t.env, 0), &10);
+    t.client.distribute_claimable(&payer, &rid(&t.env, 0));
+    t.env.ledger().with_mut(|l| l.timestamp = 50);
+    t.client.mark_paid(&rid(&t.env, 1), &4);
+    t.client.distribute_claimable(&payer, &rid(&t.env, 1));
+
+    // em 120 só o primeiro lançamento prescreveu
+    t.env.ledger().with_mut(|l| l.timestamp = 120);
+    let both = vec![&t.env, a.clone(), b.clone()];
+    assert_eq!(t.client.sweep_rewards(&fund, &both, &SweepTarget::Reserve), 10);
+    assert_eq!((t.client.claimable(&fund, &a), t.client.claimable(&fund, &b)), (2, 2));
+    assert_eq!((t.client.total_claimable(&fund), t.client.reserve_balance(&fund)), (4, 10));
+    assert_eq!(t.client.reserve_backed(&fund), 10);
+    assert_eq!(t.client.claim(&b, &fund), 2);
+    // o prescrito está no vault e pode sair da reserva
+    let manager = Address::generate(&t.env);
+    t.client.withdraw_reserve(&fund, &10, &manager, &s(&t.env, "despesas"));
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&manager), 10);
+    assert_eq!((t.client.reserve_balance(&fund), t.client.reserve_backed(&fund)), (0, 0));
+
+    // valor retido redistribuído: quem chama o sweep deposita o total
+    ft.whitelist_remove(&b);
+    t.client.mark_paid(&rid(&t.env, 2), &6);
+    t.client.distribute(&rid(&t.env, 2), &both);
+    assert_eq!(t.client.escrowed(&fund, &b), 3);
+    t.env.ledger().with_mut(|l| l.timestamp = 300);
+    assert_eq!(t.client.sweep_unclaimed(&fund, &0, &MAX_BATCH, &SweepTarget::Redistribute(funder.clone())), (3, 0));
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&funder), 0);
+    assert_eq!(t.client.total_claimable(&fund), 5);
+    assert_eq!(t.client.claimable(&fund, &a), 3);
+}
+
+#[test]
+fn waterfall_pays_buckets_before_holders() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let administrator = Address::generate(&t.env);
+    t.client.set_waterfall(&fund, &vec![
+        &t.env,
+        WaterfallBucket { kind: BucketKind::Administration, recipient: administrator.clone(), amount: BucketAmount::Bps(1_000) },
+        WaterfallBucket { kind: BucketKind::Reserve, recipient: administrator.clone(), amount: BucketAmount::Fixed(50) },
+        WaterfallBucket { kind: BucketKind::Custody, recipient: administrator.clone(), amount: BucketAmount::Fixed(10_000) },
+    ]);
+    let id = paid_receivable(&t, 1_000);
+    let hs = holders(&t, &[(1, true), (1, true)]);
+
+    let preview = t.client.preview_distribution(&id, &hs);
+    // a custódia leva só o que sobrou: nada para os cotistas
+    assert_eq!(preview.waterfall, vec![&t.env, (administrator.clone(), 100), (t.client.address.clone(), 50), (administrator, 850)]);
+    assert_eq!(preview.shares, vec![&t.env, (hs.get(0).unwrap(), 0), (hs.get(1).unwrap(), 0)]);
+
+    // faixas de taxa pagam junto (evento com 4 tópicos; o destinatário vai no dado)
+    t.client.distribute(&id, &hs);
+    assert_eq!(t.client.reserve_balance(&fund), 50);
+    assert!(matches!(t.client.get_receivable(&id).status, ReceivableStatus::Distributed));
+
+    let bad = vec![&t.env, WaterfallBucket { kind: BucketKind::Management, recipient: t.client.address.clone(), amount: BucketAmount::Bps(10_001) }];
+    assert_eq!(t.client.try_set_waterfall(&fund, &bad), Err(Ok(VaultError::Invalid)));
+}
+
+#[test]
+fn cancelled_paginated_distribution_reverses_the_waterfall() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let manager = Address::generate(&t.env);
+    t.client.set_waterfall(&fund, &vec![
+        &t.env,
+        WaterfallBucket { kind: BucketKind::Management, recipient: manager, amount: BucketAmount::Bps(1_000) },
+        WaterfallBucket { kind: BucketKind::Reserve, recipient: t.client.address.clone(), amount: BucketAmount::Bps(2_000) },
+    ]);
+    let id = paid_receivable(&t, 1_000);
+    let hs = holders(&t, &[(1, true), (1, true)]);
+    t.token.set_pause(&true);
+
+    t.client.start_distribution(&id, &hs);
+    assert_eq!(t.client.get_distribution(&id).amount, 700);
+    assert_eq!(t.client.reserve_balance(&fund), 200);
+    t.client.cancel_distribution(&id);
+    assert_eq!(t.client.reserve_balance(&fund), 0);
+
+    // reiniciar exige apagar a lista anterior e não paga a cascata duas vezes
+    assert_eq!(t.client.try_start_distribution(&id, &hs), Err(Ok(VaultError::BadStatus)));
+    assert_eq!(t.client.clear_distribution(&id, &MAX_BATCH), 0);
+    t.client.start_distribution(&id, &hs);
+    t.client.process_distribution(&id, &0, &MAX_BATCH);
+    assert!(t.client.finalize_distribution(&id));
+    assert_eq!(t.client.reserve_balance(&fund), 200);
+}
+
+#[test]
+fn commission_accrues_on_payment_and_is_funded_by_the_waterfall() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.set_commission_rate(&fund, &200);
+    t.client.set_waterfall(&fund, &vec![
+        &t.env,
+        WaterfallBucket { kind: BucketKind::Commission, recipient: t.client.address.clone(), amount: BucketAmount::Fixed(15) },
+    ]);
+    let id = paid_receivable(&t, 1_000);
+    let consultant = t.client.get_receivable(&id).consultant;
+    assert_eq!(t.client.consultant_accrued(&consultant), 20);
+
+    // cascata provisiona até 15 por pagamento; o saque fica limitado ao provisionado
+    let hs = holders(&t, &[(1, true)]);
+    t.client.distribute(&id, &hs);
+    assert_eq!(t.client.commission_pool(&fund), 15);
+
+    // a provisão só é paga com caixa depositado para as comissões
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    let manager = Address::generate(&t.env);
+    token::StellarAssetClient::new(&t.env, &asset).mint(&manager, &15);
+    token::StellarAssetClient::new(&t.env, &asset).mint(&t.client.address, &1_000);
+    t.client.set_payment_asset(&fund, &asset);
+    assert_eq!(t.client.claim_commission(&consultant, &fund), 0);
+    t.client.fund_commission(&manager, &fund, &10);
+    assert_eq!(t.client.claim_commission(&consultant, &fund), 10);
+    assert_eq!(t.client.commission_funded(&consultant, &fund), 5);
+    t.client.fund_commission(&manager, &fund, &5);
+    assert_eq!(t.client.claim_commission(&consultant, &fund), 5);
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&consultant), 15);
+    assert_eq!(t.client.commission_backed(&fund), 0);
+    assert_eq!((t.client.consultant_accrued(&consultant), t.client.commission_due(&consultant, &fund)), (5, 5));
+    assert_eq!(t.client.claim_commission(&consultant, &fund), 0);
+
+    // taxa própria do consultor prevalece sobre a do fundo
+    t.client.set_consultant_rate(&consultant, &Some(50));
+    assert_eq!(t.client.commission_rate(&consultant, &fund), 50);
+}
+
+#[test]
+fn cancel_is_refused_once_the_commission_was_claimed() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.set_commission_rate(&fund, &200);
+    t.client.set_waterfall(&fund, &vec![
+        &t.env,
+        WaterfallBucket { kind: BucketKind::Commission, recipient: t.client.address.clone(), amount: BucketAmount::Fixed(15) },
+    ]);
+    let id = paid_receivable(&t, 1_000);
+    let consultant = t.client.get_receivable(&id).consultant;
+    let hs = holders(&t, &[(1, true)]);
+    t.token.set_pause(&true);
+    t.client.start_distribution(&id, &hs);
+
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    token::StellarAssetClient::new(&t.env, &asset).mint(&consultant, &15);
+    t.client.set_payment_asset(&fund, &asset);
+    t.client.fund_commission(&consultant, &fund, &15);
+    assert_eq!(t.client.claim_commission(&consultant, &fund), 15);
+    assert_eq!(t.client.try_cancel_distribution(&id), Err(Ok(VaultError::BadStatus)));
+    assert_eq!(t.client.commission_pool(&fund), 0);
+}
+
+#[test]
+fn commission_is_funded_for_the_consultant_of_the_paid_receivable() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.set_commission_rate(&fund, &1_000);
+    t.client.set_waterfall(&fund, &vec![
+        &t.env,
+        WaterfallBucket { kind: BucketKind::Commission, recipient: t.client.address.clone(), amount: BucketAmount::Bps(10_000) },
+    ]);
+    let other = Address::generate(&t.env);
+    t.env.as_contract(&t.client.address, || {
+        t.env.storage().persistent().set(&DataKey::Cedente(s(&t.env, "CED-2")), &entity(&t.env, "CED-2", &other, Status::Approved));
+    });
+    let mut batch = with_faces(&t.env, &[1_000, 1_000]);
+    let mut it = batch.get(1).unwrap();
+    it.cedente_id = s(&t.env, "CED-2");
+    batch.set(1, it);
+    t.client.register_receivables(&batch);
+    let first = t.client.get_receivable(&rid(&t.env, 0)).consultant;
+    t.client.mark_paid(&rid(&t.env, 0), &1_000);
+    t.client.mark_paid(&rid(&t.env, 1), &1_000);
+
+    // o pagamento do título do primeiro consultor provisiona só a comissão dele
+    t.client.distribute(&rid(&t.env, 0), &holders(&t, &[(1, true)]));
+    assert_eq!(t.client.commission_pool(&fund), 100);
+    assert_eq!((t.client.commission_funded(&first, &fund), t.client.commission_funded(&other, &fund)), (100, 0));
+
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    token::StellarAssetClient::new(&t.env, &asset).mint(&other, &200);
+    t.client.set_payment_asset(&fund, &asset);
+    t.client.fund_commission(&other, &fund, &200);
+    assert_eq!(t.client.claim_commission(&other, &fund), 0);
+    assert_eq!(t.client.claim_commission(&first, &fund), 100);
+
+    t.client.distribute(&rid(&t.env, 1), &holders(&t, &[(1, true)]));
+    assert_eq!(t.client.claim_commission(&other, &fund), 100);
+    assert_eq!(t.client.commission_pool(&fund), 0);
+}
+
+#[test]
+fn distribution_holds_back_cash_until_reserve_target() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.set_reserve_config(&fund, &ReserveConfig { base: ReserveBase::Expenses(600), bps: 5_000 });
+    assert_eq!(t.client.reserve_target(&fund), 300);
+
+    let id = paid_receivable(&t, 1_000);
+    let hs = holders(&t, &[(1, true), (1, true)]);
+    let preview = t.client.preview_distribution(&id, &hs);
+    assert_eq!(preview.waterfall, vec![&t.env, (t.client.address.clone(), 300)]);
+    assert_eq!(preview.shares, vec![&t.env, (hs.get(0).unwrap(), 350), (hs.get(1).unwrap(), 350)]);
+
+    t.client.distribute(&id, &hs);
+    assert_eq!(t.client.reserve_balance(&fund), 300);
+
+    let to = Address::generate(&t.env);
+    let reason = s(&t.env, "despesa de auditoria");
+    assert_eq!(t.client.try_withdraw_reserve(&fund, &301, &to, &reason), Err(Ok(VaultError::InsufficientReserve)));
+    assert_eq!(t.client.try_withdraw_reserve(&fund, &100, &to, &s(&t.env, "")), Err(Ok(VaultError::Invalid)));
+    assert_eq!(t.client.try_withdraw_reserve(&fund, &100, &to, &reason), Err(Ok(VaultError::NoPaymentAsset)));
+
+    // a retenção da cascata é só registro: sem depósito nada sai do vault
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    t.client.set_payment_asset(&fund, &asset);
+    assert_eq!(t.client.try_withdraw_reserve(&fund, &100, &to, &reason), Err(Ok(VaultError::InsufficientReserve)));
+    let funder = Address::generate(&t.env);
+    token::StellarAssetClient::new(&t.env, &asset).mint(&funder, &400);
+    t.client.fund_reserve(&funder, &fund, &250);
+    assert_eq!((t.client.reserve_balance(&fund), t.client.reserve_backed(&fund)), (300, 250));
+    assert_eq!(t.client.try_withdraw_reserve(&fund, &251, &to, &reason), Err(Ok(VaultError::InsufficientReserve)));
+    t.client.withdraw_reserve(&fund, &100, &to, &reason);
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&to), 100);
+    t.client.fund_reserve(&funder, &fund, &150);
+    assert_eq!((t.client.reserve_balance(&fund), t.client.reserve_backed(&fund)), (300, 300));
+
+    // meta sobre o valor das cotas: o NAV marcado, não a quantidade de cotas em circulação
+    t.client.set_reserve_config(&fund, &ReserveConfig { base: ReserveBase::QuotaValue, bps: 1_000 });
+    assert_eq!(t.client.reserve_target(&fund), 0);
+    let entry = t.client.record_nav(&fund, &MAX_NAV_PAGE).unwrap();
+    assert_eq!(t.client.reserve_target(&fund), entry.nav / 10);
+}
+
+#[test]
+fn registration_requires_approved_parties_linked_to_the_fund() {
+    let t = setup();
+    let consultant = Address::generate(&t.env);
+    let register = |cedente: &str, sacado: &str| {
+        let mut input = inputs(&t.env, 1).get(0).unwrap();
+        input.cedente_id = s(&t.env, cedente);
+        input.sacado_id = s(&t.env, sacado);
+        t.client.register_receivables(&vec![&t.env, input])
+    };
+    t.env.as_contract(&t.client.address, || {
+        let store = |key: DataKey, e: EntityRecord| t.env.storage().persistent().set(&key, &e);
+        store(DataKey::Cedente(s(&t.env, "CED-P")), entity(&t.env, "CED-P", &consultant, Status::Pending));
+        store(DataKey::Cedente(s(&t.env, "CED-R")), entity(&t.env, "CED-R", &consultant, Status::Rejected));
+        let mut other = entity(&t.env, "CED-O", &consultant, Status::Approved);
+        other.fund_id = s(&t.env, "FUND-2");
+        store(DataKey::Cedente(s(&t.env, "CED-O")), other);
+        let mut other = entity(&t.env, "SAC-O", &consultant, Status::Approved);
+        other.fund_id = s(&t.env, "FUND-2");
+        store(DataKey::Sacado(s(&t.env, "SAC-O")), other);
+    });
+
+    assert_eq!(register("CED-P", "SAC-1"), BatchResult::Failed(0, VaultError::CedenteNotApproved as u32));
+    assert_eq!(register("CED-R", "SAC-1"), BatchResult::Failed(0, VaultError::CedenteNotApproved as u32));
+    assert_eq!(register("CED-O", "SAC-1"), BatchResult::Failed(0, VaultError::CedenteNotInFund as u32));
+    assert_eq!(register("CED-1", "SAC-O"), BatchResult::Failed(0, VaultError::SacadoNotInFund as u32));
+
+    t.client.suspend_entity(&EntityKind::Sacado, &s(&t.env, "SAC-1"), &s(&t.env, "inadimplência"));
+    assert_eq!(register("CED-1", "SAC-1"), BatchResult::Failed(0, VaultError::SacadoSuspended as u32));
+    t.client.reactivate_entity(&EntityKind::Sacado, &s(&t.env, "SAC-1"));
+    t.client.suspend_entity(&EntityKind::Cedente, &s(&t.env, "CED-1"), &s(&t.env, "fraude"));
+    assert_eq!(register("CED-1", "SAC-1"), BatchResult::Failed(0, VaultError::CedenteSuspended as u32));
+    t.client.reactivate_entity(&EntityKind::Cedente, &s(&t.env, "CED-1"));
+    assert_eq!(register("CED-1", "SAC-1"), BatchResult::Done(1));
+}
+
+fn invocation(t: &Setup, fn_name: &str, args: Vec<soroban_sdk::Val>) -> AuthorizedInvocation {
+    AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((t.client.address.clone(), Symbol::new(&t.env, fn_name), args)),
+        sub_invocations: std::vec![],
+    }
+}
+
+#[test]
+fn consultant_submissions_require_the_consultant_signature() {
+    let t = setup();
+    let consultant = Address::generate(&t.env);
+    let intruder = Address::generate(&t.env);
+    t.client.consultant_add(&consultant);
+    t.client.consultant_assign(&consultant, &s(&t.env, "FUND-1"));
+    let (fund, id, name) = (s(&t.env, "FUND-1"), s(&t.env, "CED-9"), s(&t.env, "Cedente"));
+
+    // só o intruso assina: a chamada em nome do consultor é recusada
+    let args = (consultant.clone(), id.clone(), name.clone(), fund.clone()).into_val(&t.env);
+    let forged = MockAuth {
+        address: &intruder,
+        invoke: &MockAuthInvoke { contract: &t.client.address, fn_name: "submit_cedente", args, sub_invokes: &[] },
+    };
+    assert!(t.client.mock_auths(&[forged]).try_submit_cedente(&consultant, &id, &name, &fund).is_err());
+
+    // o próprio consultor assina
+    let args = (consultant.clone(), id.clone(), name.clone(), fund.clone()).into_val(&t.env);
+    let signed = MockAuth {
+        address: &consultant,
+        invoke: &MockAuthInvoke { contract: &t.client.address, fn_name: "submit_cedente", args, sub_invokes: &[] },
+    };
+    t.client.mock_auths(&[signed]).submit_cedente(&consultant, &id, &name, &fund);
+    assert_eq!(t.client.get_cedente(&id).created_by, consultant);
+
+    // assinatura válida, mas o signatário não é consultor
+    t.env.mock_all_auths();
+    let sacado = s(&t.env, "SAC-9");
+    assert_eq!(t.client.try_submit_sacado(&intruder, &sacado, &name, &fund), Err(Ok(VaultError::NotConsultant)));
+    assert_eq!(t.client.try_submit_fund(&intruder, &s(&t.env, "FUND-9"), &name, &intruder), Err(Ok(VaultError::NotConsultant)));
+
+    t.client.submit_sacado(&consultant, &sacado, &name, &fund);
+    let args = (consultant.clone(), sacado.clone(), name.clone(), fund.clone()).into_val(&t.env);
+    assert_eq!(t.env.auths(), std::vec![(consultant.clone(), invocation(&t, "submit_sacado", args))]);
+
+    let fund_id = s(&t.env, "FUND-9");
+    t.client.submit_fund(&consultant, &fund_id, &name, &consultant);
+    let args = (consultant.clone(), fund_id, name, consultant.clone()).into_val(&t.env);
+    assert_eq!(t.env.auths(), std::vec![(consultant.clone(), invocation(&t, "submit_fund", args))]);
+}
+
+#[test]
+fn consultants_submit_only_in_assigned_funds_and_get_credited() {
+    let t = setup();
+    let consultant = Address::generate(&t.env);
+    t.client.consultant_add(&consultant);
+    let (fund, name) = (s(&t.env, "FUND-1"), s(&t.env, "Cedente"));
+
+    assert_eq!(t.client.try_submit_cedente(&consultant, &s(&t.env, "CED-9"), &name, &fund), Err(Ok(VaultError::NotAssigned)));
+    t.client.consultant_assign(&consultant, &fund);
+    t.client.submit_cedente(&consultant, &s(&t.env, "CED-9"), &name, &fund);
+    t.client.consultant_unassign(&consultant, &fund);
+    assert_eq!(t.client.try_submit_sacado(&consultant, &s(&t.env, "SAC-9"), &name, &fund), Err(Ok(VaultError::NotAssigned)));
+
+    // quem propõe um fundo fica designado para ele
+    t.client.submit_fund(&consultant, &s(&t.env, "FUND-9"), &name, &consultant);
+    assert!(t.client.is_assigned(&consultant, &s(&t.env, "FUND-9")));
+
+    t.client.approve_entity(&EntityKind::Cedente, &s(&t.env, "CED-9"), &true, &None);
+    let mut batch = inputs(&t.env, 3);
+    for i in 0..2 {
+        let mut it = batch.get(i).unwrap();
+        it.cedente_id = s(&t.env, "CED-9");
+        batch.set(i, it);
+    }
+    assert_eq!(t.client.register_receivables(&batch), BatchResult::Done(3));
+    assert_eq!(t.client.get_receivable(&rid(&t.env, 0)).consultant, consultant);
+    let p = t.client.consultant_production(&consultant, &fund);
+    assert_eq!((p.count, p.face_value), (2, 2_000_0000000));
+}
+
+#[test]
+fn sensitive_actions_need_m_of_n_approvals_before_expiry() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let (a, b, c) = (Address::generate(&t.env), Address::generate(&t.env), Address::generate(&t.env));
+    let outsider = Address::generate(&t.env);
+    assert_eq!(t.client.try_set_approvers(&fund, &vec![&t.env, a.clone(), b.clone()], &3, &100, &0), Err(Ok(VaultError::Invalid)));
+    t.client.set_approvers(&fund, &vec![&t.env, a.clone(), b.clone(), c.clone()], &2, &100, &1_000_0000000);
+
+    // até o limite o gestor registra sozinho; acima dele, só por proposta
+    let batch = inputs(&t.env, 2);
+    assert_eq!(t.client.register_receivables(&vec![&t.env, batch.get(0).unwrap()]), BatchResult::Done(1));
+    let mut big = batch.get(1).unwrap();
+    big.face_value = 1_000_0000001;
+    assert_eq!(t.client.try_register_receivable(&big.id, &fund, &big.cedente_id, &big.sacado_id, &big.face_value, &big.due_date, &big.debtor_address), Err(Ok(VaultError::NeedsProposal)));
+    assert_eq!(t.client.try_approve_entity(&EntityKind::Cedente, &s(&t.env, "CED-1"), &true, &None), Err(Ok(VaultError::NeedsProposal)));
+
+    assert_eq!(t.client.try_propose(&outsider, &ProposalAction::RegisterReceivable(big.clone())), Err(Ok(VaultError::NotApprover)));
+    let id = t.client.propose(&a, &ProposalAction::RegisterReceivable(big.clone()));
+    assert_eq!(t.client.try_approve_proposal(&a, &id), Err(Ok(VaultError::AlreadyApproved)));
+    assert_eq!(t.client.try_get_receivable(&big.id), Err(Ok(VaultError::NotFound)));
+    assert!(t.client.approve_proposal(&c, &id));
+    assert_eq!(t.client.get_receivable(&big.id).face_value, 1_000_0000001);
+    assert_eq!(t.client.try_approve_proposal(&b, &id), Err(Ok(VaultError::BadStatus)));
+
+    // distribuição também passa por proposta, que expira após o ttl
+    t.client.mark_paid(&rid(&t.env, 0), &1_000_0000000);
+    let hs = holders(&t, &[(1, true)]);
+    assert_eq!(t.client.try_distribute(&rid(&t.env, 0), &hs), Err(Ok(VaultError::NeedsProposal)));
+    let id = t.client.propose(&b, &ProposalAction::Distribute(rid(&t.env, 0), hs));
+    t.env.ledger().with_mut(|l| l.timestamp += 101);
+    assert_eq!(t.client.try_approve_proposal(&a, &id), Err(Ok(VaultError::Expired)));
+    assert!(matches!(t.client.get_receivable(&rid(&t.env, 0)).status, ReceivableStatus::Paid));
+
+    // todas as portas de distribuição exigem proposta
+    let r0 = rid(&t.env, 0);
+    let hs = holders(&t, &[(1, true)]);
+    assert_eq!(t.client.try_distribute_claimable(&a, &r0), Err(Ok(VaultError::NeedsProposal)));
+    assert_eq!(t.client.try_start_distribution(&r0, &hs), Err(Ok(VaultError::NeedsProposal)));
+    assert_eq!(t.client.try_distribute_installment(&r0, &0, &hs), Err(Ok(VaultError::NeedsProposal)));
+    let root = hash(&t.env, 1);
+    assert_eq!(t.client.try_publish_merkle_root(&a, &s(&t.env, "D-1"), &fund, &None, &root, &1, &1_000_000), Err(Ok(VaultError::NeedsProposal)));
+
+    // o gestor sozinho não troca nem desliga os aprovadores
+    assert_eq!(t.client.try_set_approvers(&fund, &vec![&t.env], &0, &0, &0), Err(Ok(VaultError::NeedsProposal)));
+    let id = t.client.propose(&a, &ProposalAction::SetApprovers(fund.clone(), vec![&t.env], 0, 0, 0));
+    assert!(t.client.approvers(&fund).is_some());
+    assert!(t.client.approve_proposal(&b, &id));
+    assert!(t.client.approvers(&fund).is_none());
+    t.client.distribute(&r0, &hs);
+}
+
+#[test]
+fn payment_asset_change_waits_for_timelock_and_can_be_cancelled() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let (old, new) = (Address::generate(&t.env), Address::generate(&t.env));
+    t.client.set_payment_asset(&fund, &old);
+    assert_eq!(t.client.try_set_payment_asset(&fund, &new), Err(Ok(VaultError::Timelocked)));
+
+    let op = TimelockOp::SetPaymentAsset(fund.clone(), new.clone());
+    assert_eq!(t.client.try_schedule(&op), Err(Ok(VaultError::NoTimelock)));
+    let guardian = Address::generate(&t.env);
+    assert_eq!(t.client.try_init_timelock(&guardian, &0), Err(Ok(VaultError::DelayTooShort)));
+    t.client.init_timelock(&guardian, &MIN_DELAY);
+    assert_eq!(t.client.try_schedule(&TimelockOp::SetDelay(MIN_DELAY - 1)), Err(Ok(VaultError::DelayTooShort)));
+
+    let cancelled = t.client.schedule(&op);
+    t.client.cancel(&cancelled);
+    assert!(t.client.scheduled_op(&cancelled).is_none());
+
+    let id = t.client.schedule(&op);
+    assert_eq!(t.client.try_execute(&id), Err(Ok(VaultError::NotReady)));
+    t.env.ledger().with_mut(|l| l.sequence_number += MIN_DELAY);
+    t.client.execute(&id);
+    assert_eq!(t.client.payment_asset(&fund), Some(new));
+    assert_eq!(t.client.try_execute(&id), Err(Ok(VaultError::NotFound)));
+}
+
+#[test]
+fn installments_are_paid_and_distributed_one_by_one() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let inst = s(&t.env, "INST-1");
+    let due = 1_800_000_000u64;
+    let schedule = vec![&t.env, (due, 100i128), (due + 30 * DAY, 200i128), (due + 60 * DAY, 300i128)];
+    let unordered = vec![&t.env, (due, 100i128), (due, 200i128)];
+    assert_eq!(t.client.try_register_installment_receivable(&inst, &fund, &s(&t.env, "CED-1"), &s(&t.env, "SAC-1"), &unordered, &Address::generate(&t.env)), Err(Ok(VaultError::Invalid)));
+    t.client.register_installment_receivable(&inst, &fund, &s(&t.env, "CED-1"), &s(&t.env, "SAC-1"), &schedule, &Address::generate(&t.env));
+    let r = t.client.get_receivable(&inst);
+    assert_eq!((r.face_value, r.due_date), (600, due + 60 * DAY));
+    assert_eq!(t.client.next_due(&inst).unwrap().due_date, due);
+    assert_eq!(t.client.outstanding(&inst), 600);
+
+    t.env.ledger().with_mut(|l| l.timestamp = due + 31 * DAY);
+    assert_eq!(t.client.overdue_installments(&inst), vec![&t.env, 0u32, 1]);
+
+    let hs = holders(&t, &[(30, true), (70, false)]);
+    let blocked = hs.get(1).unwrap();
+    assert_eq!(t.client.try_distribute_installment(&inst, &1, &hs), Err(Ok(VaultError::BadStatus)));
+    assert_eq!(t.client.try_mark_installment_paid(&inst, &1, &0), Err(Ok(VaultError::Invalid)));
+    assert_eq!(t.client.try_mark_installment_paid(&inst, &3, &200), Err(Ok(VaultError::NotFound)));
+    t.client.mark_installment_paid(&inst, &1, &200);
+    assert_eq!(t.client.try_mark_installment_paid(&inst, &1, &200), Err(Ok(VaultError::BadStatus)));
+    assert_eq!(t.client.overdue_installments(&inst), vec![&t.env, 0u32]);
+    assert_eq!(t.client.next_due(&inst).unwrap().due_date, due);
+    assert_eq!(t.client.outstanding(&inst), 400);
+
+    t.client.distribute_installment(&inst, &1, &hs);
+    assert_eq!(t.client.escrowed(&fund, &blocked), 140);
+    assert_eq!(t.client.try_distribute_installment(&inst, &1, &hs), Err(Ok(VaultError::BadStatus)));
+    assert!(matches!(t.client.get_receivable(&inst).status, ReceivableStatus::Pending));
+
+    // pagamento a menor não quita a parcela
+    t.client.mark_installment_paid(&inst, &0, &60);
+    assert!(matches!(t.client.installments(&inst).get(0).unwrap().status, ReceivableStatus::Pending));
+    assert_eq!(t.client.outstanding(&inst), 340);
+    assert_eq!(t.client.try_distribute_installment(&inst, &0, &hs), Err(Ok(VaultError::BadStatus)));
+    assert_eq!(t.client.try_mark_installment_paid(&inst, &0, &41), Err(Ok(VaultError::Invalid)));
+    t.client.mark_installment_paid(&inst, &0, &40);
+    t.client.mark_installment_paid(&inst, &2, &300);
+    let r = t.client.get_receivable(&inst);
+    assert!(matches!(r.status, ReceivableStatus::Paid));
+    assert_eq!(r.paid_amount, 600);
+    assert!(t.client.next_due(&inst).is_none());
+    assert_eq!(t.client.outstanding(&inst), 0);
+    assert_eq!(t.client.overdue_installments(&inst).len(), 0);
+
+    t.client.distribute_installment(&inst, &0, &hs);
+    t.client.distribute_installment(&inst, &2, &hs);
+    assert_eq!(t.client.escrowed(&fund, &blocked), 420);
+    assert!(matches!(t.client.get_receivable(&inst).status, ReceivableStatus::Distributed));
+}
+
+#[test]
+fn provision_follows_aging_buckets_net_of_payments() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let due = 1_800_000_000u64;
+    t.client.register_receivables(&inputs(&t.env, 2));
+    t.client.mark_paid(&rid(&t.env, 1), &1_000_0000000);
+    // parcelado: a 1ª parcela (vencida há mais tempo) é paga, a 2ª define a faixa
+    let inst = s(&t.env, "INST-1");
+    let schedule = vec![&t.env, (due - 40 * DAY, 100_0000000i128), (due, 300_0000000i128)];
+    t.client.register_installment_receivable(&inst, &fund, &s(&t.env, "CED-1"), &s(&t.env, "SAC-1"), &schedule, &Address::generate(&t.env));
+    t.client.mark_installment_paid(&inst, &0, &100_0000000);
+    // pagamento a menor: o título segue em aberto pelo saldo
+    t.client.mark_paid(&rid(&t.env, 0), &400_0000000);
+    assert!(matches!(t.client.get_receivable(&rid(&t.env, 0)).status, ReceivableStatus::Pending));
+    assert_eq!(t.client.outstanding(&rid(&t.env, 0)), 600_0000000);
+
+    t.env.ledger().with_mut(|l| l.timestamp = due + 20 * DAY);
+    let a = t.client.aging(&inst);
+    assert_eq!((a.days_overdue, a.bucket, a.exposure, a.provision), (20, 1, 300_0000000, 3_0000000));
+    assert_eq!(t.client.aging(&rid(&t.env, 0)).exposure, 600_0000000);
+
+    let report = t.client.provision(&fund);
+    assert_eq!(report.exposure.get(1).unwrap(), 900_0000000);
+    assert_eq!(report.total, 9_0000000);
+    assert_eq!(t.client.fund_receivables(&fund, &0, &10).len(), 3);
+
+    t.client.set_provision_table(&fund, &ProvisionTable { bounds: vec![&t.env, 10], rates: vec![&t.env, 0, 10_000] });
+    assert_eq!(t.client.provision(&fund).total, 900_0000000);
+    let bad = ProvisionTable { bounds: vec![&t.env, 30, 30], rates: vec![&t.env, 0, 1, 2] };
+    assert_eq!(t.client.try_set_provision_table(&fund, &bad), Err(Ok(VaultError::Invalid)));
+}
+
+#[test]
+fn nav_discounts_open_receivables_on_the_acquisition_curve() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.register_receivables(&inputs(&t.env, 1));
+    t.env.ledger().with_mut(|l| l.timestamp = 1_800_000_000 - 10 * DAY);
+    assert_eq!(t.client.try_set_acquisition(&rid(&t.env, 0), &1_000_0000001, &0), Err(Ok(VaultError::Invalid)));
+    assert_eq!(t.client.try_set_acquisition(&rid(&t.env, 0), &900_0000000, &0), Err(Ok(VaultError::InsufficientCash)));
+    t.env.as_contract(&t.client.address, || t.env.storage().persistent().set(&DataKey::Cash(fund.clone()), &900_0000000i128));
+    // 1% a.d. por 10 dias: 1_000 / 1,01^10
+    t.client.set_acquisition(&rid(&t.env, 0), &900_0000000, &10_000_000_000);
+    assert_eq!(t.client.cash(&fund), 0);
+    assert_eq!(t.client.try_set_acquisition(&rid(&t.env, 0), &900_0000000, &0), Err(Ok(VaultError::DuplicateId)));
+
+    let n = t.client.nav(&fund);
+    assert_eq!((n.receivables, n.provision), (9_052_869_546, 5_0000000));
+    assert_eq!(n.nav, 9_002_869_546);
+    assert_eq!(t.client.try_quota_price(&fund), Err(Ok(VaultError::NoSupply)));
+
+    t.token.set_holder(&Address::generate(&t.env), &100_0000000, &true);
+    assert_eq!(t.client.quota_price(&fund), 90_028_695);
+    t.client.record_nav(&fund, &MAX_NAV_PAGE);
+    // no vencimento o título vale o valor de face
+    t.env.ledger().with_mut(|l| l.timestamp = 1_800_000_000);
+    let entry = t.client.record_nav(&fund, &MAX_NAV_PAGE).unwrap();
+    assert_eq!((entry.nav, entry.at), (1_000_0000000 - 5_0000000, 1_800_000_000));
+    assert_eq!(t.client.nav_history(&fund, &0, &10).len(), 2);
+    assert_eq!(t.client.latest_nav(&fund).unwrap().price, entry.price);
+}
+
+#[test]
+fn nav_is_marked_in_pages_and_skips_closed_receivables() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.register_receivables(&inputs(&t.env, MAX_NAV_PAGE + 1));
+    let inst = s(&t.env, "INST-1");
+    let schedule = vec![&t.env, (1_800_000_000u64, 100i128), (1_810_000_000u64, 300i128)];
+    t.client.register_installment_receivable(&inst, &fund, &s(&t.env, "CED-1"), &s(&t.env, "SAC-1"), &schedule, &Address::generate(&t.env));
+    // título encerrado com parcela ainda Pending no storage: não entra no NAV
+    t.env.as_contract(&t.client.address, || {
+        let mut r: Receivable = t.env.storage().persistent().get(&DataKey::Receivable(inst.clone())).unwrap();
+        r.status = ReceivableStatus::WrittenOff;
+        t.env.storage().persistent().set(&DataKey::Receivable(inst.clone()), &r);
+    });
+    t.token.set_holder(&Address::generate(&t.env), &100_0000000, &true);
+    let open = (MAX_NAV_PAGE as i128 + 1) * 1_000_0000000;
+    assert_eq!(t.client.nav(&fund).receivables, open);
+
+    assert_eq!(t.client.try_record_nav(&fund, &(MAX_NAV_PAGE + 1)), Err(Ok(VaultError::BatchTooLarge)));
+    assert!(t.client.record_nav(&fund, &MAX_NAV_PAGE).is_none());
+    assert_eq!(t.client.nav_run(&fund).unwrap().cursor, MAX_NAV_PAGE);
+    // as páginas seguintes avaliam no instante da primeira
+    t.env.ledger().with_mut(|l| l.timestamp = 60);
+    let entry = t.client.record_nav(&fund, &MAX_NAV_PAGE).unwrap();
+    assert_eq!((entry.nav, entry.at), (t.client.nav(&fund).nav, 0));
+    assert!(t.client.nav_run(&fund).is_none());
+
+    // marcação abandonada por mais de um dia recomeça do início
+    t.client.record_nav(&fund, &1);
+    t.env.ledger().with_mut(|l| l.timestamp = 60 + DAY);
+    t.client.record_nav(&fund, &1);
+    let run = t.client.nav_run(&fund).unwrap();
+    assert_eq!((run.at, run.cursor), (60 + DAY, 1));
+}
+
+#[test]
+fn subscription_mints_quotas_at_nav_price() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let investor = Address::generate(&t.env);
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    token::StellarAssetClient::new(&t.env, &asset).mint(&investor, &10_000_0000000);
+    t.client.set_payment_asset(&fund, &asset);
+    assert_eq!(t.client.try_subscribe(&investor, &fund, &100_0000000), Err(Ok(VaultError::NotFound)));
+    t.client.set_subscription_terms(&fund, &SubscriptionTerms { min_amount: 100_0000000, initial_price: PRICE_SCALE });
+
+    assert_eq!(t.client.try_subscribe(&investor, &fund, &100_0000000), Err(Ok(VaultError::NotWhitelisted)));
+    t.token.set_holder(&investor, &0, &true);
+    assert_eq!(t.client.try_subscribe(&investor, &fund, &99_0000000), Err(Ok(VaultError::BelowMinimum)));
+
+    // supply zero: preço inicial de 1 por cota
+    assert_eq!(t.client.subscribe(&investor, &fund, &1_000_0000000), 1_000_0000000);
+    assert_eq!(t.client.cash(&fund), 1_000_0000000);
+
+    // com o NAV em 1.000 + 1.000 recebidos, a cota vale 2; sem NAV marcado não há preço
+    t.client.register_receivables(&inputs(&t.env, 1));
+    t.client.mark_paid(&rid(&t.env, 0), &1_000_0000000);
+    assert_eq!(t.client.quota_price(&fund), 2 * PRICE_SCALE);
+    assert_eq!(t.client.try_subscribe(&investor, &fund, &500_0000000), Err(Ok(VaultError::StaleNav)));
+    t.client.record_nav(&fund, &MAX_NAV_PAGE);
+    assert_eq!(t.client.subscribe(&investor, &fund, &500_0000000), 250_0000000);
+    assert_eq!(t.token.balance(&investor), 1_250_0000000);
+
+    t.token.set_max_supply(&1_300_0000000);
+    assert_eq!(t.client.try_subscribe(&investor, &fund, &200_0000000), Err(Ok(VaultError::MaxSupplyExceeded)));
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&t.client.address), 1_500_0000000);
+}
+
+#[test]
+fn redemptions_wait_for_notice_and_are_gated_pro_rata() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    t.client.set_payment_asset(&fund, &asset);
+    t.client.set_subscription_terms(&fund, &SubscriptionTerms { min_amount: 0, initial_price: PRICE_SCALE });
+    t.token.set_holder(&t.client.address, &0, &true);
+    let investors = [Address::generate(&t.env), Address::generate(&t.env)];
+    for i in &investors {
+        token::StellarAssetClient::new(&t.env, &asset).mint(i, &1_000_0000000);
+        t.token.set_holder(i, &0, &true);
+        t.client.subscribe(i, &fund, &1_000_0000000);
+    }
+    // D+30, até 25% do NAV por período de 30 dias
+    t.client.set_redemption_terms(&fund, &RedemptionTerms { notice: 30 * DAY, gate_bps: 2_500, period: 30 * DAY, min_quotas: 10_0000000 });
+    // pedidos de pó não ocupam a fila
+    assert_eq!(t.client.try_request_redemption(&investors[0], &fund, &1), Err(Ok(VaultError::BelowMinimum)));
+    for i in &investors { t.client.request_redemption(i, &fund, &400_0000000); }
+    assert_eq!(t.token.balance(&t.client.address), 800_0000000);
+    assert_eq!(t.client.try_settle_redemptions(&fund, &MAX_SETTLE_PAGE), Err(Ok(VaultError::StaleNav)));
+    t.client.record_nav(&fund, &MAX_NAV_PAGE);
+    assert_eq!(t.client.settle_redemptions(&fund, &MAX_SETTLE_PAGE), Some(0));
+
+    // demanda de 800 contra gate de 500: cada pedido recebe 5/8 e o resto fica na fila
+    t.env.ledger().with_mut(|l| l.timestamp = 30 * DAY);
+    assert_eq!(t.client.try_settle_redemptions(&fund, &MAX_SETTLE_PAGE), Err(Ok(VaultError::StaleNav)));
+    t.client.record_nav(&fund, &MAX_NAV_PAGE);
+    assert_eq!(t.client.settle_redemptions(&fund, &MAX_SETTLE_PAGE), Some(500_0000000));
+    let payment = token::Client::new(&t.env, &asset);
+    assert_eq!(payment.balance(&investors[0]), 250_0000000);
+    assert_eq!(t.client.redemption(&0).unwrap().quotas, 150_0000000);
+    assert_eq!((t.client.cash(&fund), t.token.total_supply()), (1_500_0000000, 1_500_0000000));
+    assert_eq!(t.client.settle_redemptions(&fund, &MAX_SETTLE_PAGE), Some(0));
+
+    // novo período: gate de 375 cobre os 300 restantes
+    t.env.ledger().with_mut(|l| l.timestamp = 60 * DAY);
+    t.client.record_nav(&fund, &MAX_NAV_PAGE);
+    assert_eq!(t.client.settle_redemptions(&fund, &MAX_SETTLE_PAGE), Some(300_0000000));
+    assert_eq!(payment.balance(&investors[1]), 400_0000000);
+    assert_eq!(t.client.redemption_queue(&fund).len(), 0);
+    assert_eq!(t.client.latest_nav(&fund).unwrap().price, PRICE_SCALE);
+}
+
+#[test]
+fn locked_quotas_pass_their_rewards_to_the_requester() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let ft = real_token(&t);
+    ft.set_minter(&t.client.address, &true);
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    t.client.set_payment_asset(&fund, &asset);
+    t.client.set_subscription_terms(&fund, &SubscriptionTerms { min_amount: 0, initial_price: PRICE_SCALE });
+    t.client.set_redemption_terms(&fund, &RedemptionTerms { notice: 30 * DAY, gate_bps: 10_000, period: 30 * DAY, min_quotas: 10_0000000 });
+    ft.whitelist_add(&t.client.address);
+    let (a, b) = (Address::generate(&t.env), Address::generate(&t.env));
+    for i in [&a, &b] {
+        ft.whitelist_add(i);
+        token::StellarAssetClient::new(&t.env, &asset).mint(i, &1_000_0000000);
+    }
+    t.client.subscribe(&a, &fund, &1_000_0000000);
+    t.client.record_nav(&fund, &MAX_NAV_PAGE);
+    t.client.subscribe(&b, &fund, &1_000_0000000);
+    t.client.request_redemption(&a, &fund, &500_0000000);
+
+    // rendimento lançado com metade das cotas de `a` travadas no vault
+    let id = paid_receivable(&t, 300);
+    let payer = Address::generate(&t.env);
+    token::StellarAssetClient::new(&t.env, &asset).mint(&payer, &300);
+    t.client.distribute_claimable(&payer, &id);
+    assert_eq!((ft.pending_reward(&t.client.address), ft.pending_reward(&a)), (75, 75));
+
+    // antes do prazo só o rendimento é repassado; na liquidação, as cotas
+    t.client.record_nav(&fund, &MAX_NAV_PAGE);
+    assert_eq!(t.client.settle_redemptions(&fund, &MAX_SETTLE_PAGE), Some(0));
+    let payment = token::Client::new(&t.env, &asset);
+    assert_eq!(payment.balance(&a), 75);
+    assert_eq!((ft.pending_reward(&t.client.address), t.client.total_claimable(&fund)), (0, 225));
+    t.env.ledger().with_mut(|l| l.timestamp = 30 * DAY);
+    t.client.record_nav(&fund, &MAX_NAV_PAGE);
+    assert_eq!(t.client.settle_redemptions(&fund, &MAX_SETTLE_PAGE), Some(500_0000000));
+    assert_eq!(payment.balance(&a), 500_0000075);
+    assert_eq!(t.client.claim(&a, &fund), 75);
+    assert_eq!(t.client.claim(&b, &fund), 150);
+}
+
+#[test]
+fn full_redemption_queue_is_settled_in_pages_within_limits() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let ft = real_token(&t);
+    ft.set_minter(&t.client.address, &true);
+    let asset = t.env.register_stellar_asset_contract_v2(Address::generate(&t.env)).address();
+    t.client.set_payment_asset(&fund, &asset);
+    t.client.set_subscription_terms(&fund, &SubscriptionTerms { min_amount: 0, initial_price: PRICE_SCALE });
+    t.client.set_redemption_terms(&fund, &RedemptionTerms { notice: 30 * DAY, gate_bps: 10_000, period: 30 * DAY, min_quotas: 10_0000000 });
+    ft.whitelist_add(&t.client.address);
+    let investors: std::vec::Vec<Address> = (0..MAX_REDEMPTIONS).map(|_| Address::generate(&t.env)).collect();
+    for (n, i) in investors.iter().enumerate() {
+        ft.whitelist_add(i);
+        token::StellarAssetClient::new(&t.env, &asset).mint(i, &100_0000000);
+        t.client.subscribe(i, &fund, &100_0000000);
+        if n == 0 { t.client.record_nav(&fund, &MAX_NAV_PAGE); }
+        t.client.request_redemption(i, &fund, &50_0000000);
+    }
+    assert_eq!(t.client.try_request_redemption(&investors[0], &fund, &10_0000000), Err(Ok(VaultError::QueueFull)));
+
+    // rendimento lançado com metade das cotas de cada cotista travadas no vault
+    let id = paid_receivable(&t, 100_0000000);
+    let payer = Address::generate(&t.env);
+    token::StellarAssetClient::new(&t.env, &asset).mint(&payer, &100_0000000);
+    t.client.distribute_claimable(&payer, &id);
+
+    t.env.ledger().with_mut(|l| l.timestamp = 30 * DAY);
+    t.client.record_nav(&fund, &MAX_NAV_PAGE);
+    assert_eq!(t.client.try_settle_redemptions(&fund, &(MAX_SETTLE_PAGE + 1)), Err(Ok(VaultError::BatchTooLarge)));
+    let paid = loop {
+        let page = t.client.settle_redemptions(&fund, &MAX_SETTLE_PAGE);
+        assert_within_limits(&t.env);
+        if let Some(paid) = page { break paid; }
+    };
+    assert_eq!(paid, MAX_REDEMPTIONS as i128 * 50_0000000);
+    assert_eq!((t.client.redemption_queue(&fund).len(), t.client.cash(&fund)), (0, MAX_REDEMPTIONS as i128 * 50_0000000));
+    assert!(t.client.settle_run(&fund).is_none());
+    assert_eq!(token::Client::new(&t.env, &asset).balance(&investors[0]), 50_0000000 + 1_0000000);
+    assert_eq!(ft.total_supply(), MAX_REDEMPTIONS as i128 * 50_0000000);
+}
+
+#[test]
+fn entity_rejection_resubmission_and_suspension_are_recorded() {
+    let t = setup();
+    let consultant = Address::generate(&t.env);
+    t.client.consultant_add(&consultant);
+    let id = s(&t.env, "CED-2");
+    t.env.as_contract(&t.client.address, || {
+        t.env.storage().persistent().set(&DataKey::Cedente(id.clone()), &entity(&t.env, "CED-2", &consultant, Status::Pending));
+    });
+    t.env.ledger().with_mut(|l| l.timestamp = 10);
+
+    assert_eq!(t.client.try_approve_entity(&EntityKind::Cedente, &id, &false, &None), Err(Ok(VaultError::Invalid)));
+    t.client.approve_entity(&EntityKind::Cedente, &id, &false, &Some(s(&t.env, "CNPJ irregular")));
+    assert_eq!(t.client.get_cedente(&id).rejection_reason, Some(s(&t.env, "CNPJ irregular")));
+    assert_eq!(t.client.try_suspend_entity(&EntityKind::Cedente, &id, &s(&t.env, "x")), Err(Ok(VaultError::BadStatus)));
+
+    let stranger = Address::generate(&t.env);
+    t.client.consultant_add(&stranger);
+    assert_eq!(t.client.try_resubmit_entity(&stranger, &EntityKind::Cedente, &id, &None), Err(Ok(VaultError::Unauthorized)));
+    assert_eq!(t.client.try_resubmit_entity(&consultant, &EntityKind::Cedente, &id, &None), Err(Ok(VaultError::NotAssigned)));
+    t.client.consultant_assign(&consultant, &s(&t.env, "FUND-1"));
+    t.client.resubmit_entity(&consultant, &EntityKind::Cedente, &id, &Some(s(&t.env, "Cedente SA")));
+    let e = t.client.get_cedente(&id);
+    assert!(matches!(e.status, Status::Pending));
+    assert_eq!((e.name, e.rejection_reason), (s(&t.env, "Cedente SA"), None));
+
+    t.env.ledger().with_mut(|l| l.timestamp = 20);
+    t.client.approve_entity(&EntityKind::Cedente, &id, &true, &None);
+    t.client.suspend_entity(&EntityKind::Cedente, &id, &s(&t.env, "protestos"));
+    let e = t.client.get_cedente(&id);
+    assert!(matches!(e.status, Status::Suspended));
+    assert_eq!((e.approved_by, e.approved_at), (Some(t.client.get_admin()), 20));
+    t.client.reactivate_entity(&EntityKind::Cedente, &id);
+    assert!(matches!(t.client.get_cedente(&id).status, Status::Approved));
+
+    let history = t.client.status_history(&EntityKind::Cedente, &id);
+    let statuses: std::vec::Vec<u32> = history.iter().map(|c| c.status as u32).collect();
+    assert_eq!(statuses, [2, 0, 1, 3, 1]);
+    assert_eq!(history.get(3).unwrap().reason, Some(s(&t.env, "protestos")));
+    assert_eq!(history.get(1).unwrap().by, consultant);
+}
+
+fn class(env: &Env, kind: QuotaClassKind, benchmark_bps: u32, principal: i128) -> QuotaClass {
+    QuotaClass { kind, token: Address::generate(env), benchmark_bps, principal, losses: 0, shortfall: 0, last_accrual: 0 }
+}
+
+#[test]
+fn reconfiguring_classes_drops_the_old_class_tokens() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let old = vec![&t.env, class(&t.env, QuotaClassKind::Senior, 1_000, 0), class(&t.env, QuotaClassKind::Subordinated, 0, 0)];
+    t.client.set_quota_classes(&fund, &old);
+    let mut new = old.clone();
+    new.set(0, class(&t.env, QuotaClassKind::Senior, 1_000, 0));
+    t.client.set_quota_classes(&fund, &new);
+
+    let mapped = |token: Address| t.env.as_contract(&t.client.address, || t.env.storage().persistent().has(&DataKey::ClassToken(token)));
+    assert!(!mapped(old.get(0).unwrap().token));
+    assert!(mapped(new.get(0).unwrap().token));
+    assert!(mapped(new.get(1).unwrap().token));
+}
+
+#[test]
+fn senior_is_paid_up_to_benchmark_and_subordinated_absorbs_losses() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.set_quota_classes(&fund, &vec![
+        &t.env,
+        class(&t.env, QuotaClassKind::Senior, 1_000, 1_000_000),
+        class(&t.env, QuotaClassKind::Subordinated, 0, 500_000),
+    ]);
+    let wrong_order = vec![&t.env, class(&t.env, QuotaClassKind::Subordinated, 0, 1), class(&t.env, QuotaClassKind::Senior, 0, 1)];
+    assert_eq!(t.client.try_set_quota_classes(&fund, &wrong_order), Err(Ok(VaultError::Invalid)));
+
+    // meio ano a 10% a.a. sobre 1_000_000 = 50_000 devidos à sênior
+    t.env.ledger().with_mut(|l| l.timestamp = YEAR / 2);
+    assert_eq!(t.client.class_split(&fund, &100_000), vec![&t.env, (QuotaClassKind::Senior, 50_000), (QuotaClassKind::Subordinated, 50_000)]);
+    assert_eq!(t.client.class_split(&fund, &30_000), vec![&t.env, (QuotaClassKind::Senior, 30_000), (QuotaClassKind::Subordinated, 0)]);
+
+    t.client.register_receivables(&inputs(&t.env, 1));
+    // perda de 1_000_0000000 consome toda a subordinada e toda a sênior
+    assert_eq!(t.client.write_off(&rid(&t.env, 0)), 1_000_0000000);
+    let classes = t.client.quota_classes(&fund);
+    assert_eq!((classes.get(1).unwrap().principal, classes.get(1).unwrap().losses), (0, 500_000));
+    assert_eq!((classes.get(0).unwrap().principal, classes.get(0).unwrap().losses), (0, 1_000_000));
+    assert!(matches!(t.client.get_receivable(&rid(&t.env, 0)).status, ReceivableStatus::WrittenOff));
+}
+
+#[test]
+fn blocked_senior_mint_is_flagged_before_issuance() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    let id = t.env.register(fund_token::FundToken, ());
+    let ft = fund_token::FundTokenClient::new(&t.env, &id);
+    ft.initialize(&Address::generate(&t.env), &s(&t.env, "Senior"), &s(&t.env, "SEN"), &1_000_000_0000000);
+    ft.set_mint_guard(&t.client.address);
+    let investor = Address::generate(&t.env);
+    ft.whitelist_add(&investor);
+
+    let mut senior = class(&t.env, QuotaClassKind::Senior, 0, 1_000_000);
+    senior.token = id.clone();
+    t.client.set_quota_classes(&fund, &vec![&t.env, senior, class(&t.env, QuotaClassKind::Subordinated, 0, 250_000)]);
+    t.client.set_min_subordination(&fund, &2_000);
+
+    // a consulta prévia e a própria emissão recusada deixam o alerta gravado
+    let alert: Vec<soroban_sdk::Val> = (Symbol::new(&t.env, "sub_alert"), fund.clone()).into_val(&t.env);
+    assert!(!t.client.check_mint(&id, &100));
+    assert!(t.env.events().all().iter().any(|(c, topics, _)| c == t.client.address && topics == alert));
+    assert!(!ft.mint(&investor, &100));
+    assert!(t.env.events().all().iter().any(|(c, topics, _)| c == t.client.address && topics == alert));
+    assert_eq!(ft.balance(&investor), 0);
+    assert_eq!(t.client.quota_classes(&fund).get(0).unwrap().principal, 1_000_000);
+}
+
+#[test]
+fn write_off_is_final_and_class_history_survives_reconfiguration() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.set_quota_classes(&fund, &vec![
+        &t.env,
+        class(&t.env, QuotaClassKind::Senior, 1_000, 1_000_000),
+        class(&t.env, QuotaClassKind::Subordinated, 0, 500_000),
+    ]);
+    let inst = s(&t.env, "INST-1");
+    let schedule = vec![&t.env, (1_800_000_000u64, 100i128), (1_810_000_000u64, 300i128)];
+    t.client.register_installment_receivable(&inst, &fund, &s(&t.env, "CED-1"), &s(&t.env, "SAC-1"), &schedule, &Address::generate(&t.env));
+    t.client.mark_installment_paid(&inst, &0, &100);
+
+    assert_eq!(t.client.write_off(&inst), 300);
+    assert!(matches!(t.client.installments(&inst).get(1).unwrap().status, ReceivableStatus::WrittenOff));
+    assert_eq!(t.client.outstanding(&inst), 0);
+    // pagamento depois da baixa não devolve o título a Paid
+    assert_eq!(t.client.try_mark_installment_paid(&inst, &1, &300), Err(Ok(VaultError::BadStatus)));
+    assert!(matches!(t.client.get_receivable(&inst).status, ReceivableStatus::WrittenOff));
+
+    // reconfigurar as classes mantém a perda da subordinada e os 50_000 devidos à sênior
+    t.env.ledger().with_mut(|l| l.timestamp = YEAR / 2);
+    t.client.set_quota_classes(&fund, &vec![
+        &t.env,
+        class(&t.env, QuotaClassKind::Senior, 500, 2_000_000),
+        class(&t.env, QuotaClassKind::Subordinated, 0, 499_700),
+    ]);
+    let classes = t.client.quota_classes(&fund);
+    assert_eq!((classes.get(0).unwrap().shortfall, classes.get(1).unwrap().losses), (50_000, 300));
+    assert_eq!(t.client.class_split(&fund, &60_000), vec![&t.env, (QuotaClassKind::Senior, 50_000), (QuotaClassKind::Subordinated, 10_000)]);
+}
+
+#[test]
+fn senior_issuance_cannot_breach_min_subordination() {
+    let t = setup();
+    let fund = s(&t.env, "FUND-1");
+    t.client.set_quota_classes(&fund, &vec![
+        &t.env,
+        class(&t.env, QuotaClassKind::Senior, 0, 1_000_000),
+        class(&t.env, QuotaClassKind::Subordinated, 0, 250_000),
+    ]);
+    t.client.set_min_subordination(&fund, &2_000);
+    assert_eq!(t.client.subordination_ratio(&fund), 2_000);
+
+    let classes = t.client.quota_classes(&fund);
+    let (senior, sub) = (classes.get(0).unwrap().token, classes.get(1).unwrap().token);
+    assert!(!t.client.on_mint(&senior, &100, &0));
+    assert_eq!(t.client.quota_classes(&fund).get(0).unwrap().principal, 1_000_000);
+
+    assert!(t.client.on_mint(&sub, &1_000, &0));
+    assert!(t.client.on_mint(&senior, &1_000, &0));
+    assert_eq!(t.client.subordination_ratio(&fund), 2_004);
+
+    // classe sem principal: a emissão vale cotas × preço inicial, não a quantidade de cotas
+    t.client.set_subscription_terms(&fund, &SubscriptionTerms { min_amount: 0, initial_price: 2 * PRICE_SCALE });
+    let mut zeroed = t.client.quota_classes(&fund);
+    let mut c = zeroed.get(1).unwrap();
+    c.principal = 0;
+    zeroed.set(1, c);
+    t.client.set_quota_classes(&fund, &zeroed);
+    assert!(t.client.on_mint(&sub, &1_000, &0));
+    assert_eq!(t.client.quota_classes(&fund).get(1).unwrap().principal, 2_000);
 }